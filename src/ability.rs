@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+const COMET: &str = include_str!("../abilities/comet.json");
+
+/// Bundled definitions of notable die rolling abilities, by name.
+const PRESETS: [(&str, &str); 4] = [
+    ("comet", COMET),
+    ("delina", include_str!("../abilities/delina.json")),
+    ("ancient-copper-dragon", include_str!("../abilities/ancient-copper-dragon.json")),
+    ("bucknards-everfull-purse", include_str!("../abilities/bucknards-everfull-purse.json")),
+];
+
+/// A single effect of a die roll result, applied in the listed order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Effect {
+    /// Puts or removes loyalty counters
+    Loyalty(i32),
+    /// Creates 1/1 green Squirrel tokens with haste, before the token replacement effects
+    Squirrels(u32),
+    /// Deals damage equal to the loyalty to a creature or player chosen by the strategy
+    LoyaltyDamage,
+    /// Deals fixed damage to a creature or player chosen by the strategy
+    Damage(u32),
+    /// Allows activating the ability more times this turn
+    ExtraActivations(u32),
+    /// Returns a card with at most this mana value from graveyard to hand
+    ReturnCard(u32),
+    /// Creates Treasure tokens, adding their mana to the mana available this turn
+    Treasures(u32),
+    /// Opens the top Attraction of the Attraction deck
+    OpenAttraction,
+}
+
+/// The effects of the results from `from` to `to`, inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Band {
+    pub from: u32,
+    pub to: u32,
+    pub effects: Vec<Effect>,
+}
+
+/// An ability rolling a die and doing something depending on the result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ability {
+    pub name: String,
+    /// Is the source red, for Torbran
+    pub red: bool,
+    /// Number of sides on the rolled die
+    pub die: u32,
+    pub bands: Vec<Band>,
+}
+
+impl Ability {
+    /// Comet, Stellar Pup's 0 ability, from the bundled definition.
+    pub fn comet() -> Self {
+        static ABILITY: OnceLock<Ability> = OnceLock::new();
+        ABILITY
+            .get_or_init(|| COMET.parse().expect("invalid bundled Comet definition"))
+            .clone()
+    }
+
+    /// One of the bundled definitions by name, like comet or delina.
+    pub fn preset(name: &str) -> Option<Self> {
+        if name == "comet" {
+            return Some(Ability::comet());
+        }
+
+        PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, json)| json.parse().expect("invalid bundled ability definition"))
+    }
+
+    /// Names of the bundled definitions.
+    pub fn presets() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Effects of the roll, none if no band covers it.
+    pub fn effects(&self, roll: u32) -> &[Effect] {
+        self.bands
+            .iter()
+            .find(|band| (band.from..=band.to).contains(&roll))
+            .map(|band| band.effects.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The most loyalty a single result can lose.
+    pub fn worst_loyalty_change(&self) -> i32 {
+        self.bands
+            .iter()
+            .map(|band| band.effects.iter().map(Effect::loyalty).sum())
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Effect {
+    fn loyalty(&self) -> i32 {
+        match self {
+            Effect::Loyalty(change) => *change,
+            _ => 0,
+        }
+    }
+}
+
+impl Default for Ability {
+    fn default() -> Self {
+        Ability::comet()
+    }
+}
+
+impl FromStr for Ability {
+    type Err = String;
+
+    /// Parses an ability definition from JSON.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ability: Ability =
+            serde_json::from_str(s).map_err(|err| format!("invalid ability definition: {err}"))?;
+
+        if ability.die == 0 {
+            return Err(format!("invalid ability definition: {} rolls a zero-sided die", ability.name));
+        }
+
+        Ok(ability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_definition() {
+        let ability: Ability = r#"{
+            "name": "Coin flip",
+            "red": false,
+            "die": 2,
+            "bands": [{ "from": 2, "to": 2, "effects": [{ "damage": 3 }, { "loyalty": -1 }] }]
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(ability.effects(2), [Effect::Damage(3), Effect::Loyalty(-1)]);
+        assert!(ability.effects(1).is_empty());
+        assert_eq!(ability.worst_loyalty_change(), -1);
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        let zero_sided = r#"{ "name": "Nothing", "red": false, "die": 0, "bands": [] }"#;
+        assert!(zero_sided.parse::<Ability>().is_err());
+        assert!(r#"{ "name": "Nothing" }"#.parse::<Ability>().is_err());
+    }
+
+    #[test]
+    fn comet_is_the_bundled_definition() {
+        let comet = Ability::comet();
+        assert_eq!(comet.die, 6);
+        assert_eq!(comet.effects(3), [Effect::Loyalty(-1), Effect::ReturnCard(2)]);
+        assert_eq!(comet.effects(6), [Effect::Loyalty(1), Effect::ExtraActivations(2)]);
+        assert_eq!(comet.worst_loyalty_change(), -2);
+    }
+
+    #[test]
+    fn every_preset_is_valid() {
+        for name in Ability::presets() {
+            let ability = Ability::preset(name).unwrap();
+            assert!(ability.die > 0, "{name} has no die");
+        }
+
+        assert_eq!(Ability::preset("comet"), Some(Ability::comet()));
+        assert_eq!(Ability::preset("pixie-guide"), None);
+    }
+
+    #[test]
+    fn delina_rolls_again_on_15_or_higher() {
+        let delina = Ability::preset("delina").unwrap();
+        assert_eq!(delina.effects(14), [Effect::Squirrels(1)]);
+        assert_eq!(delina.effects(15), [Effect::Squirrels(1), Effect::ExtraActivations(1)]);
+        assert_eq!(delina.worst_loyalty_change(), 0);
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::ability::Effect;
+
+/// Attractions are visited with a six-sided die.
+pub const VISIT_DIE: u32 = 6;
+
+/// An Unfinity Attraction, visited when the visit roll is one of its lit numbers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attraction {
+    pub name: String,
+    /// Numbers from 1 to 6 lit on this printing
+    pub lit: Vec<u32>,
+    /// Effects of visiting the Attraction, applied in the listed order
+    pub visit: Vec<Effect>,
+}
+
+impl Attraction {
+    pub fn is_lit(&self, roll: u32) -> bool {
+        self.lit.contains(&roll)
+    }
+}
+
+/// The Attraction deck, shuffled at the start of the game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttractionDeck {
+    pub attractions: Vec<Attraction>,
+    /// Attractions opened before the turn starts
+    pub open: usize,
+}
+
+impl FromStr for AttractionDeck {
+    type Err = String;
+
+    /// Parses the Attractions of the deck from a JSON list.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let attractions: Vec<Attraction> =
+            serde_json::from_str(s).map_err(|err| format!("invalid Attraction deck: {err}"))?;
+
+        if let Some(attraction) = attractions
+            .iter()
+            .find(|attraction| attraction.lit.iter().any(|lit| !(1..=VISIT_DIE).contains(lit)))
+        {
+            return Err(format!(
+                "invalid Attraction deck: {} lights a number outside 1 to {VISIT_DIE}",
+                attraction.name
+            ));
+        }
+
+        Ok(AttractionDeck {
+            attractions,
+            open: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_an_attraction_deck() {
+        let deck: AttractionDeck =
+            r#"[{ "name": "Balloon Stand", "lit": [2, 6], "visit": [{ "squirrels": 1 }] }]"#
+                .parse()
+                .unwrap();
+
+        assert_eq!(deck.open, 0);
+        assert!(deck.attractions[0].is_lit(6));
+        assert!(!deck.attractions[0].is_lit(3));
+        assert_eq!(deck.attractions[0].visit, [Effect::Squirrels(1)]);
+    }
+
+    #[test]
+    fn rejects_numbers_outside_the_visit_die() {
+        for lit in ["[0]", "[7]"] {
+            let deck = format!(r#"[{{ "name": "Broken", "lit": {lit}, "visit": [] }}]"#);
+            assert!(deck.parse::<AttractionDeck>().is_err(), "{lit} was accepted");
+        }
+    }
+}
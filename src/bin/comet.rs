@@ -1,10 +1,33 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::Env;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::sync::OnceLock;
 
 use rayon::prelude::*;
 
-use comet::game::{Game, Outcome};
+use comet::ability::Ability;
+use comet::attraction::AttractionDeck;
+use comet::card::Card;
+use comet::combat::CombatTiming;
+use comet::decklist::{Decklist, KnownCard};
+use comet::goldfish::{self, Deck, Goldfish};
+use comet::optimiser::{Method, Objective, Optimiser};
+use comet::interaction::{Answer, Interaction};
+use comet::scenario::{Scenario, ScenarioFile, Varied};
+use comet::script::Script;
+use comet::simulator::Settings;
+use comet::solver::{Policy, Solution};
+use comet::strategy;
+use comet::sweep::{self, Axis, Format, Summary};
+use comet::tournament::{self, Entrant, Tournament};
+use comet::turns::{self, OpponentModel};
+use comet::game::{
+    Board, CopyEffect, DamageReplacement, Game, GameResult, ManaPolicy, Outcome, PaidActivation,
+    ReplacementOrder, SacrificeOutlet, TokenReplacement, SPARK_DOUBLE_LOYALTY,
+};
 
 #[macro_use]
 extern crate log;
@@ -12,44 +35,468 @@ extern crate log;
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Number of games to simulate
-    #[clap(short, long, value_parser, default_value_t = 100)]
+    #[clap(short, long, global = true, value_parser, default_value_t = 100)]
     games: usize,
 
+    /// Die rolling ability to activate instead of Comet's, either a JSON file or one of the
+    /// presets: comet, delina, ancient-copper-dragon or bucknards-everfull-purse
+    #[clap(long, global = true, value_parser = ability_file)]
+    ability: Option<Ability>,
+
     /// Print game actions debug output (slow)
-    #[clap(short, long, action)]
+    #[clap(short, long, global = true, action)]
     verbose: bool,
 
     /// Should combat damage using squirrels be included
-    #[clap(short, long, action)]
+    #[clap(short, long, global = true, action)]
     squirrels: bool,
 
-    /// Extra roll advantage effects
-    #[clap(short, long, value_parser, default_value_t = 0)]
-    advantage: usize,
+    /// Extra roll advantage effects, or comma separated values and ranges like 0..20 for sweep
+    #[clap(short, long, global = true, value_parser, default_value = "0")]
+    advantage: Axis,
+
+    /// Starting loyalty of the planeswalker, or comma separated values and ranges like 3..9 for
+    /// sweep
+    #[clap(short, long, global = true, value_parser, default_value = "5")]
+    loyalty: Axis,
+
+    /// Target damage to deal, or comma separated values and ranges like 20,40 for sweep
+    #[clap(short, long, global = true, value_parser, default_value = "20")]
+    damage: Axis,
+
+    /// Token doubling effects, like Anointed Procession or Doubling Season
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    token_doublers: usize,
+
+    /// Creature token doubling effects, like Parallel Lives
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    creature_token_doublers: usize,
+
+    /// Chatterfang, Squirrel General is on the battlefield
+    #[clap(long, global = true, action)]
+    chatterfang: bool,
+
+    /// Torbran, Thane of Red Fell effects
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    torbran: usize,
+
+    /// Damage doubling effects, like Dictate of the Twin Gods
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    damage_doublers: usize,
+
+    /// Fiery Emancipation effects
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    fiery_emancipation: usize,
+
+    /// Order of damage replacement effects: listed, most or least damage
+    #[clap(long, global = true, value_parser, default_value = "least")]
+    damage_order: ReplacementOrder,
+
+    /// Additional loyalty activations each turn, like from Oath of Teferi
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    extra_activations: u32,
+
+    /// Spark Double copies of Comet, each entering with one more loyalty than Comet's printed 5
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    spark_doubles: usize,
+
+    /// The Chain Veil effects
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    chain_veils: usize,
+
+    /// Rings of Brighthearth effects
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    rings: usize,
+
+    /// Spend mana on paid activations or copies first: activations or copies
+    #[clap(long, global = true, value_parser, default_value = "activations")]
+    mana_policy: ManaPolicy,
+
+    /// Damage dealt to each opponent whenever you roll dice, like from Brazen Dwarf
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    damage_on_roll: u32,
+
+    /// Comma separated cards in graveyard: pixie-guide, barbarian-class, wyll, brazen-dwarf or other
+    #[clap(long, global = true, value_parser, value_delimiter = ',')]
+    graveyard: Vec<Card>,
+
+    /// Comma separated toughness of each of the opponent's blockers
+    #[clap(long, global = true, value_parser, value_delimiter = ',')]
+    blockers: Vec<u32>,
+
+    /// Squirrels already on the battlefield that can attack this turn
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    attacking_squirrels: u32,
+
+    /// +1/+1 effects for Squirrels, like Deep Forest Hermit
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    squirrel_anthems: u32,
+
+    /// Is Comet activated before or after combat
+    #[clap(long, global = true, value_parser, default_value = "before")]
+    combat: CombatTiming,
+
+    /// Comma separated sacrifice outlets: goblin-bombardment, viscera-seer, damage:<N> or scry:<N>
+    #[clap(long, global = true, value_parser, value_delimiter = ',')]
+    sacrifice_outlets: Vec<SacrificeOutlet>,
+
+    /// Comma separated life totals of each opponent, like 40,40,40 for Commander, instead of a
+    /// single opponent at the target damage
+    #[clap(long, global = true, value_parser, value_delimiter = ',')]
+    opponents: Vec<u32>,
+
+    /// Chance of the opponent countering each activation, like with Stifle
+    #[clap(long, global = true, value_parser = chance, default_value_t = 0.0)]
+    counter_chance: f64,
+
+    /// Chance of the opponent removing Comet in response to each activation
+    #[clap(long, global = true, value_parser = chance, default_value_t = 0.0)]
+    response_removal_chance: f64,
+
+    /// A single answer the opponent holds up for the most damaging moment: counter or removal
+    #[clap(long, global = true, value_parser)]
+    held_answer: Option<Answer>,
 
-    /// Starting loyalty of the planeswalker
-    #[clap(short, long, value_parser, default_value_t = 5)]
-    loyalty: i32,
+    /// JSON file listing the Attractions in the Attraction deck
+    #[clap(long, global = true, value_parser = attraction_deck)]
+    attractions: Option<AttractionDeck>,
 
-    /// Target damage to deal
-    #[clap(short, long, value_parser, default_value_t = 20)]
-    damage: u32,
+    /// Attractions opened before the turn starts
+    #[clap(long, global = true, value_parser, default_value_t = 0)]
+    open_attractions: usize,
+
+    /// Comma separated Rhai script files with custom card effects
+    #[clap(long, global = true, value_parser = script_file, value_delimiter = ',')]
+    scripts: Vec<Script>,
+
+    /// MTGA or MTGO decklist file, putting the cards the simulator knows on the battlefield on
+    /// top of the other options
+    #[clap(long, global = true, value_parser = decklist_file)]
+    decklist: Option<Decklist>,
+
+    /// Card detected in the decklist to leave off the battlefield, by its full or short name like
+    /// Torbran. Can be repeated.
+    #[clap(long, global = true, value_parser)]
+    disable_card: Vec<String>,
+
+    /// Mana available for paying costs during the turn
+    #[clap(short, long, global = true, value_parser, default_value_t = 0)]
+    mana: u32,
+
+    /// Parameter of the starting state sampled for every game, like loyalty=4..7 for a uniform
+    /// range or life=20:1,30:2 for a histogram of values and weights. The parameters are loyalty,
+    /// life, advantage, mana and squirrels. Can be repeated.
+    #[clap(long, value_parser)]
+    vary: Vec<Varied>,
+
+    /// JSON scenario file with custom card scripts and parameters varied like with --vary, added
+    /// to the other options
+    #[clap(long, global = true, value_parser = scenario_file)]
+    scenario: Option<ScenarioFile>,
+
+    #[clap(long, global = true, value_parser, default_value = "default", help = strategy_help())]
+    strategy: String,
+
+    /// Seed for the dice, making the results reproducible
+    #[clap(long, global = true, value_parser)]
+    seed: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Solve the optimal decisions with value iteration and print the decision table
+    Solve {
+        /// Damage already dealt in the decision table
+        #[clap(long, value_parser, default_value_t = 0)]
+        dealt: u32,
+    },
+    /// Play several strategies on identical dice and compare the results
+    CompareStrategies {
+        #[clap(
+            long,
+            value_parser,
+            value_delimiter = ',',
+            default_value = "default,lethal,optimal",
+            help = strategies_help()
+        )]
+        strategies: Vec<String>,
+    },
+    /// Activate Comet over several turns, with the opponents answering it in between
+    Turns {
+        /// Number of turns to play
+        #[clap(long, value_parser, default_value_t = 3)]
+        turns: usize,
+
+        /// Chance of the opponents attacking Comet on their turns
+        #[clap(long, value_parser = chance, default_value_t = 0.0)]
+        attack_chance: f64,
+
+        /// Damage dealt to Comet when the opponents attack it
+        #[clap(long, value_parser, default_value_t = 0)]
+        attack_damage: u32,
+
+        /// Chance of the opponents removing Comet on their turns
+        #[clap(long, value_parser = chance, default_value_t = 0.0)]
+        removal_chance: f64,
+    },
+    /// Play the --decklist from the opening hand on, casting Comet and the known cards on curve
+    Goldfish {
+        /// Number of turns to play
+        #[clap(long, value_parser, default_value_t = 8)]
+        turns: usize,
+
+        /// Draw a card on the first turn
+        #[clap(long, action)]
+        on_the_draw: bool,
+    },
+    /// Find the support cards from the card registry maximising the objective for the starting state
+    Optimise {
+        /// Number of support cards to choose
+        #[clap(long, value_parser, default_value_t = 3)]
+        slots: usize,
+
+        /// Maximise the win probability or the expected damage: win or damage
+        #[clap(long, value_parser, default_value = "win")]
+        objective: Objective,
+
+        /// Card to choose from by its full or short name, instead of every card in the registry.
+        /// Can be repeated.
+        #[clap(long, value_parser)]
+        candidate: Vec<String>,
+
+        /// Number of the best packages to list
+        #[clap(long, value_parser, default_value_t = 10)]
+        top: usize,
+    },
+    /// Simulate every combination of the --advantage, --loyalty and --damage values and print a
+    /// table of the results
+    Sweep {
+        /// Output format: markdown, csv or json
+        #[clap(long, value_parser, default_value = "markdown")]
+        format: Format,
+    },
+}
+
+impl Args {
+    fn game(&self) -> Game {
+        self.settings().game()
+    }
+
+    fn settings(&self) -> Settings {
+        let mut settings = self.base_settings();
+
+        for card in self.known_cards().into_iter().filter(|card| self.is_enabled(card)) {
+            if let Some(modifier) = card.modifier {
+                modifier.apply(&mut settings);
+            }
+        }
+
+        settings
+    }
+
+    /// The first of the --advantage values, the only one outside of sweep.
+    fn advantage(&self) -> usize {
+        self.advantage.values[0] as usize
+    }
+
+    fn loyalty(&self) -> i32 {
+        self.loyalty.values[0] as i32
+    }
+
+    fn damage(&self) -> u32 {
+        self.damage.values[0]
+    }
+
+    /// The parameters varied with --vary and by the scenario file.
+    fn varied(&self) -> Vec<Varied> {
+        self.vary
+            .iter()
+            .chain(self.scenario.iter().flat_map(|scenario| &scenario.varied))
+            .cloned()
+            .collect()
+    }
+
+    /// Only the simulation without a subcommand varies parameters.
+    fn check_varied(&self) -> Result<(), String> {
+        if self.command.is_some() && !self.varied().is_empty() {
+            return Err("varied parameters of the scenario are only supported without a subcommand".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Only sweep takes several values for the swept parameters.
+    fn check_axes(&self) -> Result<(), String> {
+        if matches!(self.command, Some(Command::Sweep { .. })) {
+            return Ok(());
+        }
+
+        let axes = [
+            ("advantage", &self.advantage),
+            ("loyalty", &self.loyalty),
+            ("damage", &self.damage),
+        ];
+        match axes.iter().find(|(_, axis)| axis.values.len() > 1) {
+            Some((name, _)) => Err(format!("several values for --{name} are only supported by sweep")),
+            None => Ok(()),
+        }
+    }
+
+    /// Settings without the cards detected in the decklist.
+    fn base_settings(&self) -> Settings {
+        Settings {
+            squirrels: self.squirrels,
+            advantage: self.advantage(),
+            loyalty: self.loyalty(),
+            damage: self.damage(),
+            board: self.board(),
+        }
+    }
+
+    fn known_cards(&self) -> Vec<&'static KnownCard> {
+        self.decklist
+            .as_ref()
+            .map(Decklist::known_cards)
+            .unwrap_or_default()
+    }
+
+    fn is_enabled(&self, card: &KnownCard) -> bool {
+        !self.disable_card.iter().any(|name| card.is_named(name))
+    }
+
+    fn board(&self) -> Board {
+        let mut token_replacements = vec![TokenReplacement::Doubler; self.token_doublers];
+        token_replacements.extend(vec![TokenReplacement::CreatureDoubler; self.creature_token_doublers]);
+        if self.chatterfang {
+            token_replacements.push(TokenReplacement::Chatterfang);
+        }
+
+        let damage_replacements = [
+            (DamageReplacement::Torbran, self.torbran),
+            (DamageReplacement::Doubler, self.damage_doublers),
+            (DamageReplacement::FieryEmancipation, self.fiery_emancipation),
+        ]
+        .into_iter()
+        .flat_map(|(replacement, count)| vec![replacement; count])
+        .collect();
+
+        Board {
+            ability: self.ability.clone().unwrap_or_default(),
+            token_replacements,
+            damage_replacements,
+            damage_order: self.damage_order,
+            extra_activations: self.extra_activations,
+            extra_comets: vec![SPARK_DOUBLE_LOYALTY; self.spark_doubles],
+            paid_activations: vec![PaidActivation::CHAIN_VEIL; self.chain_veils],
+            copy_effects: vec![CopyEffect::RINGS_OF_BRIGHTHEARTH; self.rings],
+            mana_policy: self.mana_policy,
+            damage_on_roll: self.damage_on_roll,
+            graveyard: self.graveyard.clone(),
+            blockers: self.blockers.clone(),
+            squirrels: self.attacking_squirrels,
+            squirrel_anthems: self.squirrel_anthems,
+            combat_timing: self.combat,
+            sacrifice_outlets: self.sacrifice_outlets.clone(),
+            opponents: self.opponents.clone(),
+            interaction: Interaction {
+                counter_chance: self.counter_chance,
+                removal_chance: self.response_removal_chance,
+                held: self.held_answer,
+            },
+            attractions: AttractionDeck {
+                open: self.open_attractions,
+                ..self.attractions.clone().unwrap_or_default()
+            },
+            scripts: self
+                .scripts
+                .iter()
+                .chain(self.scenario.iter().flat_map(|scenario| &scenario.scripts))
+                .cloned()
+                .collect(),
+            mana: self.mana,
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Args::parse();
     init_logger(cli.verbose);
+    cli.check_axes()?;
+    cli.check_varied()?;
+    if !matches!(cli.command, Some(Command::Goldfish { .. })) {
+        print_known_cards(&cli);
+    }
+
+    match cli.command {
+        Some(Command::Solve { dealt }) => solve(&cli, dealt),
+        Some(Command::CompareStrategies { ref strategies }) => compare_strategies(&cli, strategies),
+        Some(Command::Turns {
+            turns,
+            attack_chance,
+            attack_damage,
+            removal_chance,
+        }) => {
+            let opponents = OpponentModel {
+                attack_chance,
+                attack_damage,
+                removal_chance,
+            };
+            simulate_turns(&cli, turns, opponents)
+        }
+        Some(Command::Goldfish { turns, on_the_draw }) => {
+            simulate_goldfish(&cli, Goldfish { turns, on_the_draw })
+        }
+        Some(Command::Optimise {
+            slots,
+            objective,
+            ref candidate,
+            top,
+        }) => optimise(&cli, slots, objective, candidate, top),
+        Some(Command::Sweep { format }) => {
+            let points = sweep::grid(&cli.advantage, &cli.loyalty, &cli.damage);
+            simulate_sweep(&cli, points, format)
+        }
+        None => simulate(&cli),
+    }
+}
 
+fn simulate(cli: &Args) -> Result<(), Box<dyn Error>> {
     let simulated_games = cli.games;
+    let strategy = strategy::built_in(&cli.strategy, &cli.game())?;
+    let varied = cli.varied();
+    if !varied.is_empty() {
+        single_state_strategy(cli, "with varied parameters")?;
+    }
 
-    let results: Vec<_> = (0..simulated_games)
+    let scenario = Scenario::new(&cli.settings(), &varied);
+    let (samples, results): (Vec<_>, Vec<_>) = (0..simulated_games)
         .into_par_iter()
-        .map(|_| {
-            let mut game = Game::new(cli.squirrels, cli.advantage, cli.loyalty, cli.damage);
-            game.run()
+        .map(|index| {
+            if varied.is_empty() {
+                let mut game = cli.game().with_strategy(strategy.clone());
+                if let Some(seed) = cli.seed {
+                    game = game.with_seed(seed.wrapping_add(index as u64));
+                }
+                return (Vec::new(), game.run());
+            }
+
+            let mut rng = match cli.seed {
+                Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(index as u64)),
+                None => StdRng::from_entropy(),
+            };
+            let (settings, sample) = scenario.sample(&mut rng);
+            let mut game = settings
+                .game()
+                .with_strategy(strategy.clone())
+                .with_seed(rng.gen());
+
+            (sample, game.run())
         })
-        .collect();
+        .unzip();
 
     let total_wins: usize = results
         .iter()
@@ -65,22 +512,77 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut total_rolls = 0;
     let mut total_squirrels = 0;
     let mut total_returns = 0;
+    let mut total_extra_activations = 0;
+    let mut total_paid_activations = 0;
+    let mut total_copies = 0;
+    let mut total_casts = 0;
+    let mut total_survived = 0;
+    let mut total_end_loyalty = 0;
+    let mut total_blockers_killed = 0;
+    let mut total_blocked = 0;
+    let mut total_sacrificed = 0;
+    let mut total_scry = 0;
+    let mut total_countered = 0;
+    let mut total_comets_alive = 0;
+    let mut total_treasures = 0;
+    let attractions = cli.board().attractions.attractions;
+    let mut total_visits = vec![0; attractions.len()];
+    let mut total_removed = 0;
+    let mut total_eliminated = vec![0; cli.opponents.len() + 1];
+    let mut total_life_left = vec![0; cli.opponents.len()];
 
-    for game in results {
-        total_damage += if cli.squirrels {
-            game.damage + game.squirrels
-        } else {
-            game.damage
-        };
+    for game in results.iter() {
+        total_damage += game.total_damage;
         total_rolls += game.rolls;
         total_squirrels += game.squirrels;
         total_returns += game.returns;
+        total_extra_activations += game.extra_activations;
+        total_paid_activations += game.paid_activations;
+        total_copies += game.copies;
+        total_casts += game.casts;
+        total_blockers_killed += game.blockers_killed;
+        total_blocked += game.blocked;
+        total_sacrificed += game.sacrificed;
+        total_scry += game.scry;
+        total_countered += game.countered;
+        total_treasures += game.treasures;
+        for (total, visits) in total_visits.iter_mut().zip(&game.visits) {
+            *total += visits;
+        }
+        total_comets_alive += game.loyalties.iter().filter(|loyalty| **loyalty > 0).count();
+        if game.removed {
+            total_removed += 1;
+        }
+        for eliminated in total_eliminated.iter_mut().take(game.eliminated + 1) {
+            *eliminated += 1;
+        }
+        for (total, life) in total_life_left.iter_mut().zip(&game.life_left) {
+            *total += life;
+        }
+        if game.loyalty > 0 {
+            total_survived += 1;
+            total_end_loyalty += game.loyalty;
+        }
     }
 
     let average_returns = total_returns as f32 / simulated_games as f32;
     let average_damage = total_damage as f32 / simulated_games as f32;
     let average_rolls = total_rolls as f32 / simulated_games as f32;
     let average_squirrels = total_squirrels as f32 / simulated_games as f32;
+    let average_extra_activations = total_extra_activations as f32 / simulated_games as f32;
+    let average_paid_activations = total_paid_activations as f32 / simulated_games as f32;
+    let average_copies = total_copies as f32 / simulated_games as f32;
+    let average_casts = total_casts as f32 / simulated_games as f32;
+    let survival_percentage = 100.0 * total_survived as f32 / simulated_games as f32;
+    let average_end_loyalty = total_end_loyalty as f32 / usize::max(total_survived, 1) as f32;
+    let average_blockers_killed = total_blockers_killed as f32 / simulated_games as f32;
+    let average_blocked = total_blocked as f32 / simulated_games as f32;
+    let average_sacrificed = total_sacrificed as f32 / simulated_games as f32;
+    let average_scry = total_scry as f32 / simulated_games as f32;
+    let average_countered = total_countered as f32 / simulated_games as f32;
+    let average_comets_alive = total_comets_alive as f32 / simulated_games as f32;
+    let average_treasures = total_treasures as f32 / simulated_games as f32;
+    let removed_percentage = 100.0 * total_removed as f32 / simulated_games as f32;
 
     info!("=======================[ RESULTS ]==========================");
     info!("                 Win percentage: {win_percentage:.2}%");
@@ -88,11 +590,512 @@ fn main() -> Result<(), Box<dyn Error>> {
     info!("              Average squirrels: {average_squirrels:.2}");
     info!("                  Average rolls: {average_rolls:.2}");
     info!("                Average returns: {average_returns:.2}");
+    info!("      Average extra activations: {average_extra_activations:.2}");
+    info!("       Average paid activations: {average_paid_activations:.2}");
+    info!("                 Average copies: {average_copies:.2}");
+    info!("                  Average casts: {average_casts:.2}");
+    info!("           Comet survived turns: {survival_percentage:.2}%");
+    info!("  Average loyalty when survived: {average_end_loyalty:.2}");
+    info!("       Average Comets surviving: {average_comets_alive:.2}");
+    info!("        Average blockers killed: {average_blockers_killed:.2}");
+    info!("      Average squirrels blocked: {average_blocked:.2}");
+    info!("   Average squirrels sacrificed: {average_sacrificed:.2}");
+    info!("                   Average scry: {average_scry:.2}");
+    info!("  Average countered activations: {average_countered:.2}");
+    info!("                  Comet removed: {removed_percentage:.2}%");
+    info!("              Average treasures: {average_treasures:.2}");
+    for (attraction, visits) in attractions.iter().zip(&total_visits) {
+        let label = format!("Average visits, {}", attraction.name);
+        let average = *visits as f32 / simulated_games as f32;
+        info!("{label:>31}: {average:.2}");
+    }
+    if cli.opponents.len() > 1 {
+        for (count, eliminated) in total_eliminated.iter().enumerate().skip(1) {
+            let label = format!("Eliminated {count} or more opponents");
+            let percentage = 100.0 * *eliminated as f32 / simulated_games as f32;
+            info!("{label:>31}: {percentage:.2}%");
+        }
+        for (index, life_left) in total_life_left.iter().enumerate() {
+            let label = format!("Average life left, opponent {}", index + 1);
+            let average = *life_left as f32 / simulated_games as f32;
+            info!("{label:>31}: {average:.2}");
+        }
+    }
     info!("============================================================");
 
+    if !varied.is_empty() {
+        print_parameters(&varied, &samples, &results);
+    }
+
     Ok(())
 }
 
+fn print_parameters(varied: &[Varied], samples: &[Vec<u32>], results: &[GameResult]) {
+    info!("=====================[ PARAMETERS ]=========================");
+    for (index, varied) in varied.iter().enumerate() {
+        let mut breakdown: BTreeMap<u32, (usize, usize, u32)> = BTreeMap::new();
+        for (sample, result) in samples.iter().zip(results) {
+            let (games, wins, damage) = breakdown.entry(sample[index]).or_default();
+            *games += 1;
+            *damage += result.total_damage;
+            if let Outcome::Win = result.outcome {
+                *wins += 1;
+            }
+        }
+
+        if index > 0 {
+            info!("------------------------------------------------------------");
+        }
+        info!(" {:>9} |    Games |   Win %  | Avg damage", varied.parameter);
+        for (value, (games, wins, damage)) in breakdown {
+            info!(
+                " {value:>9} | {games:>8} | {:>7.2}% | {:>10.2}",
+                100.0 * wins as f32 / games as f32,
+                damage as f32 / games as f32,
+            );
+        }
+    }
+    info!("============================================================");
+}
+
+fn solve(cli: &Args, dealt: u32) -> Result<(), Box<dyn Error>> {
+    let game = cli.game();
+    Solution::supports(&game)?;
+    let activations = game.free_activations_left();
+
+    let optimal = Solution::new(&game, Policy::Optimal);
+    let naive = Solution::new(&game, Policy::Naive);
+
+    let dealt = game.total_damage() + dealt;
+    let optimal_percentage = 100.0 * optimal.value(cli.loyalty(), activations, dealt);
+    let naive_percentage = 100.0 * naive.value(cli.loyalty(), activations, dealt);
+    let given_up = optimal_percentage - naive_percentage;
+
+    info!("======================[ SOLUTION ]==========================");
+    info!("         Optimal win percentage: {optimal_percentage:.4}%");
+    info!("           Naive win percentage: {naive_percentage:.4}%");
+    info!("     Given up by the naive line: {given_up:.4}%");
+    info!("============================================================");
+    info!(" Loyalty | Activations |   Win %  | Keep order");
+    info!("---------+-------------+----------+-----------------------");
+
+    let max_loyalty = i32::min(optimal.max_loyalty(), cli.loyalty() + 6);
+    for loyalty in 1..=max_loyalty {
+        for activations in 1..=3 {
+            let win_percentage = 100.0 * optimal.value(loyalty, activations, dealt);
+            let values = optimal.face_values(loyalty, activations - 1, dealt);
+            let faces = optimal.preference(loyalty, activations - 1, dealt);
+
+            let mut keep_order = faces[0].to_string();
+            for pair in faces.windows(2) {
+                let tied = values[pair[0] as usize - 1] - values[pair[1] as usize - 1] < 1e-12;
+                keep_order.push_str(if tied { " = " } else { " > " });
+                keep_order.push_str(&pair[1].to_string());
+            }
+
+            info!(" {loyalty:>7} | {activations:>11} | {win_percentage:>7.3}% | {keep_order}");
+        }
+    }
+
+    info!("============================================================");
+
+    Ok(())
+}
+
+fn compare_strategies(cli: &Args, names: &[String]) -> Result<(), Box<dyn Error>> {
+    let game = cli.game();
+    let entrants = names
+        .iter()
+        .map(|name| Ok(Entrant::new(name, strategy::built_in(name, &game)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    let rounds: Vec<_> = (0..cli.games as u64)
+        .into_par_iter()
+        .map(|index| tournament::play_round(&entrants, seed.wrapping_add(index), || cli.game()))
+        .collect();
+
+    let tournament = Tournament::new(&entrants, &rounds);
+
+    info!("=====================[ COMPARISON ]=========================");
+    info!("                          Games: {}", tournament.games);
+    info!("                           Seed: {seed}");
+    info!("------------------------------------------------------------");
+    info!(" Strategy             |    Win %  |  Avg damage");
+    for standing in tournament.standings.iter() {
+        info!(
+            " {:<20} | {:>8.2}% | {:>11.2}",
+            standing.name, standing.win_percentage, standing.average_damage
+        );
+    }
+    info!("------------------------------------------------------------");
+    for comparison in tournament.comparisons.iter() {
+        info!(
+            " {} vs {}: win {:+.2}% (p = {:.4} {}), damage {:+.2} (p = {:.4} {})",
+            comparison.second,
+            comparison.first,
+            comparison.win_difference,
+            comparison.win_p_value,
+            tournament::significance(comparison.win_p_value),
+            comparison.damage_difference,
+            comparison.damage_p_value,
+            tournament::significance(comparison.damage_p_value),
+        );
+    }
+    info!("============================================================");
+
+    Ok(())
+}
+
+fn simulate_turns(cli: &Args, turns: usize, opponents: OpponentModel) -> Result<(), Box<dyn Error>> {
+    single_state_strategy(cli, "over several turns")?;
+
+    let settings = cli.settings();
+    let strategy = strategy::built_in(&cli.strategy, &cli.game())?;
+    let seed = cli.seed.unwrap_or_else(rand::random);
+
+    let results: Vec<_> = (0..cli.games as u64)
+        .into_par_iter()
+        .map(|index| {
+            turns::play(
+                &settings,
+                strategy.clone(),
+                turns,
+                opponents,
+                seed.wrapping_add(index),
+            )
+        })
+        .collect();
+
+    let mut won_on = vec![0; turns + 1];
+    let mut survived = vec![0; turns + 1];
+    for result in results.iter() {
+        if let Some(turn) = result.won_turn {
+            won_on[turn] += 1;
+        }
+        for count in survived.iter_mut().take(result.survived + 1) {
+            *count += 1;
+        }
+    }
+
+    let games = usize::max(cli.games, 1) as f32;
+    let total_wins: usize = won_on.iter().sum();
+    let win_percentage = 100.0 * total_wins as f32 / games;
+
+    info!("========================[ TURNS ]===========================");
+    info!("                 Win percentage: {win_percentage:.2}%");
+    info!("                           Seed: {seed}");
+    info!("------------------------------------------------------------");
+    info!(" Turn |  Won on turn |  Won by turn | Comet survived");
+    let mut won_by = 0;
+    for turn in 1..=turns {
+        won_by += won_on[turn];
+        info!(
+            " {turn:>4} | {:>11.2}% | {:>11.2}% | {:>13.2}%",
+            100.0 * won_on[turn] as f32 / games,
+            100.0 * won_by as f32 / games,
+            100.0 * survived[turn] as f32 / games,
+        );
+    }
+    info!("============================================================");
+
+    Ok(())
+}
+
+fn simulate_goldfish(cli: &Args, goldfish: Goldfish) -> Result<(), Box<dyn Error>> {
+    let decklist = cli
+        .decklist
+        .as_ref()
+        .ok_or("goldfishing needs a decklist, given with --decklist")?;
+    single_state_strategy(cli, "for goldfishing")?;
+
+    let deck = Deck::new(decklist)?;
+    if deck.has_few_lands() {
+        warn!(
+            "Only {} lands were found in the deck. The lands missing from the card registry are never played, list them under a Lands header to count them.",
+            deck.lands()
+        );
+    }
+
+    let settings = cli.base_settings();
+    let strategy = strategy::built_in(&cli.strategy, &cli.game())?;
+    let seed = cli.seed.unwrap_or_else(rand::random);
+
+    let results: Vec<_> = (0..cli.games as u64)
+        .into_par_iter()
+        .map(|index| {
+            goldfish::play(
+                &deck,
+                &settings,
+                strategy.clone(),
+                goldfish,
+                seed.wrapping_add(index),
+            )
+        })
+        .collect();
+
+    let mut cast_on = vec![0; goldfish.turns + 1];
+    let mut won_on = vec![0; goldfish.turns + 1];
+    let mut total_mulligans = 0;
+    for result in results.iter() {
+        if let Some(turn) = result.cast_turn {
+            cast_on[turn] += 1;
+        }
+        if let Some(turn) = result.won_turn {
+            won_on[turn] += 1;
+        }
+        total_mulligans += result.mulligans;
+    }
+
+    let games = usize::max(cli.games, 1) as f32;
+    let total_wins: usize = won_on.iter().sum();
+    let win_percentage = 100.0 * total_wins as f32 / games;
+    let turn_sum: usize = won_on.iter().enumerate().map(|(turn, wins)| turn * wins).sum();
+    let average_kill_turn = turn_sum as f32 / usize::max(total_wins, 1) as f32;
+    let average_mulligans = total_mulligans as f32 / games;
+
+    info!("======================[ GOLDFISH ]==========================");
+    info!("                 Win percentage: {win_percentage:.2}%");
+    info!("              Average kill turn: {average_kill_turn:.2}");
+    info!("              Average mulligans: {average_mulligans:.2}");
+    info!("                  Lands in deck: {}", deck.lands());
+    info!("          Unknown cards in deck: {}", deck.blanks());
+    info!("                           Seed: {seed}");
+    info!("------------------------------------------------------------");
+    info!(" Turn |   Comet cast |  Comet by | Won on turn |  Won by turn");
+    let (mut cast_by, mut won_by) = (0, 0);
+    for turn in 1..=goldfish.turns {
+        cast_by += cast_on[turn];
+        won_by += won_on[turn];
+        info!(
+            " {turn:>4} | {:>11.2}% | {:>8.2}% | {:>10.2}% | {:>11.2}%",
+            100.0 * cast_on[turn] as f32 / games,
+            100.0 * cast_by as f32 / games,
+            100.0 * won_on[turn] as f32 / games,
+            100.0 * won_by as f32 / games,
+        );
+    }
+    info!("============================================================");
+
+    Ok(())
+}
+
+fn optimise(
+    cli: &Args,
+    slots: usize,
+    objective: Objective,
+    names: &[String],
+    top: usize,
+) -> Result<(), Box<dyn Error>> {
+    let candidates = if names.is_empty() {
+        KnownCard::registry().iter().collect()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                KnownCard::registry()
+                    .iter()
+                    .find(|card| card.is_named(name) && card.modifier.is_some())
+                    .ok_or_else(|| format!("unknown support card \"{name}\""))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    let optimiser = Optimiser::new(&cli.settings(), &candidates, objective, &cli.strategy, cli.games, seed)?;
+    let optimisation = optimiser.optimise(slots, |packages| {
+        packages
+            .par_iter()
+            .map(|package| optimiser.evaluate(package))
+            .collect()
+    })?;
+
+    let format = |value: f64| match objective {
+        Objective::Win => format!("{:.2}%", 100.0 * value),
+        Objective::Damage => format!("{value:.2}"),
+    };
+    let names = |cards: &[&KnownCard]| {
+        cards
+            .iter()
+            .map(|card| card.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    };
+
+    info!("=====================[ OPTIMISER ]==========================");
+    info!("                         Method: {}", optimisation.method);
+    info!("             Packages evaluated: {}", optimisation.evaluated);
+    if optimisation.method == Method::Simulation {
+        info!("                          Games: {}", cli.games);
+        info!("                           Seed: {seed}");
+    }
+    info!("------------------------------------------------------------");
+    info!(" Rank | {:>9} | Package", if objective == Objective::Win { "Win %" } else { "Damage" });
+    for (rank, package) in optimisation.packages.iter().take(top).enumerate() {
+        info!(" {:>4} | {:>9} | {}", rank + 1, format(package.value), names(&package.cards));
+    }
+    info!("------------------------------------------------------------");
+    info!(" Card in the best package         |   Without | Contribution");
+    for contribution in optimisation.contributions.iter() {
+        info!(
+            " {:<32} | {:>9} | {:>+12.2}",
+            contribution.card.name,
+            format(contribution.without),
+            scale(objective, contribution.contribution),
+        );
+    }
+    info!("------------------------------------------------------------");
+    info!(" Alternative                      | Replacing                        |     Value | Difference");
+    let best = optimisation.packages[0].value;
+    for alternative in optimisation.alternatives.iter() {
+        info!(
+            " {:<32} | {:<32} | {:>9} | {:>+10.2}",
+            alternative.card.name,
+            alternative.replaces.name,
+            format(alternative.value),
+            scale(objective, alternative.value - best),
+        );
+    }
+    info!("============================================================");
+
+    Ok(())
+}
+
+/// Win probabilities are shown as percentages.
+fn scale(objective: Objective, value: f64) -> f64 {
+    match objective {
+        Objective::Win => 100.0 * value,
+        Objective::Damage => value,
+    }
+}
+
+fn simulate_sweep(cli: &Args, points: Vec<sweep::Point>, format: Format) -> Result<(), Box<dyn Error>> {
+    let settings = cli.settings();
+    let seed = cli.seed.unwrap_or_else(rand::random);
+
+    let summaries = points
+        .par_iter()
+        .map(|point| {
+            let settings = point.settings(&settings);
+            let strategy = strategy::built_in(&cli.strategy, &settings.game())?;
+
+            let summary = (0..cli.games as u64)
+                .into_par_iter()
+                .map(|index| {
+                    settings
+                        .game()
+                        .with_strategy(strategy.clone())
+                        .with_seed(seed.wrapping_add(index))
+                        .run()
+                })
+                .fold(Summary::default, |summary, result| summary.record(&result))
+                .reduce(Summary::default, Summary::merge);
+
+            Ok((*point, summary))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    println!("{}", sweep::render(&summaries, format).trim_end());
+
+    Ok(())
+}
+
+fn print_known_cards(cli: &Args) {
+    let Some(decklist) = &cli.decklist else {
+        return;
+    };
+
+    let known_cards = cli.known_cards();
+    info!("======================[ DECKLIST ]==========================");
+    info!("                  Cards in deck: {}", decklist.size());
+    info!("                    Known cards: {}", known_cards.len());
+    info!("------------------------------------------------------------");
+    for card in known_cards {
+        let toggle = if cli.is_enabled(card) { "x" } else { " " };
+        if let Some(modifier) = card.modifier {
+            info!(" [{toggle}] {} ({modifier})", card.name);
+        }
+    }
+    info!("============================================================");
+}
+
+/// Rejects the optimal strategy where the starting state changes from game to game, as it is
+/// solved for the single starting state given by the options.
+fn single_state_strategy(cli: &Args, used: &str) -> Result<(), String> {
+    if cli.strategy == "optimal" {
+        return Err(format!(
+            "the optimal strategy is solved for a single starting state and can't be used {used}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Help of --strategy, listing the strategies `strategy::built_in` accepts.
+fn strategy_help() -> &'static str {
+    static HELP: OnceLock<String> = OnceLock::new();
+    HELP.get_or_init(|| format!("Strategy making the decisions: {}", strategy::BUILT_IN))
+}
+
+/// Help of --strategies, listing the strategies `strategy::built_in` accepts.
+fn strategies_help() -> &'static str {
+    static HELP: OnceLock<String> = OnceLock::new();
+    HELP.get_or_init(|| format!("Comma separated strategies: {}", strategy::BUILT_IN))
+}
+
+fn ability_file(path: &str) -> Result<Ability, String> {
+    if let Some(ability) = Ability::preset(path) {
+        return Ok(ability);
+    }
+
+    std::fs::read_to_string(path)
+        .map_err(|err| {
+            let presets: Vec<_> = Ability::presets().collect();
+            format!(
+                "failed to read \"{path}\": {err}, expected a JSON file or one of the presets: {}",
+                presets.join(", ")
+            )
+        })?
+        .parse()
+}
+
+fn attraction_deck(path: &str) -> Result<AttractionDeck, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read \"{path}\": {err}"))?
+        .parse()
+}
+
+fn decklist_file(path: &str) -> Result<Decklist, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read \"{path}\": {err}"))?
+        .parse()
+}
+
+fn scenario_file(path: &str) -> Result<ScenarioFile, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read \"{path}\": {err}"))?
+        .parse()
+}
+
+fn script_file(path: &str) -> Result<Script, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read \"{path}\": {err}"))?;
+
+    Script::new(path, &source)
+}
+
+fn chance(s: &str) -> Result<f64, String> {
+    let chance: f64 = s
+        .parse()
+        .map_err(|err| format!("invalid chance \"{s}\": {err}"))?;
+
+    if (0.0..=1.0).contains(&chance) {
+        Ok(chance)
+    } else {
+        Err(format!("invalid chance {chance}, expected a value from 0 to 1"))
+    }
+}
+
 fn init_logger(verbose: bool) {
     let default_level = if verbose { "debug" } else { "info" };
 
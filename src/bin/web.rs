@@ -3,11 +3,12 @@ use std::collections::BTreeMap;
 use gloo_worker::{Spawnable, WorkerBridge};
 use log::debug;
 use wasm_bindgen::JsCast;
-use web_sys::{EventTarget, HtmlInputElement};
+use web_sys::{EventTarget, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
 use yew::prelude::*;
 
-use comet::game::{GameResult, Outcome};
-use comet::simulator::{Cmd, Simulator, Status};
+use comet::decklist::{Decklist, KnownCard};
+use comet::game::{Board, DamageReplacement, GameResult, Outcome, ReplacementOrder, TokenReplacement};
+use comet::simulator::{Cmd, Settings, Simulator, Status};
 
 #[derive(Debug)]
 pub enum Msg {
@@ -15,7 +16,14 @@ pub enum Msg {
     ChangeAdvantage(usize),
     ChangeLoyalty(i32),
     ChangeDamage(u32),
+    ChangeTokenDoublers(usize),
     ToggleSquirrels,
+    ToggleChatterfang,
+    ToggleTorbran,
+    ToggleFieryEmancipation,
+    ChangeDamageOrder(ReplacementOrder),
+    ChangeDecklist(String),
+    ToggleKnownCard(usize),
     BeginSimulation,
     CancelSimulation,
     UpdateProgress(usize, usize, Vec<GameResult>),
@@ -63,6 +71,24 @@ pub struct App {
     /// Damage required to win for win
     damage: u32,
 
+    /// Token doubling effects on the battlefield
+    token_doublers: usize,
+
+    /// Is Chatterfang, Squirrel General on the battlefield
+    chatterfang: bool,
+
+    /// Is Torbran, Thane of Red Fell on the battlefield
+    torbran: bool,
+
+    /// Is Fiery Emancipation on the battlefield
+    fiery_emancipation: bool,
+
+    /// Order the damage replacement effects are applied in
+    damage_order: ReplacementOrder,
+
+    /// Cards detected in the pasted decklist, and are they on the battlefield
+    known_cards: Vec<(&'static KnownCard, bool)>,
+
     /// Error message from simulation
     error_msg: Option<String>,
 
@@ -80,13 +106,55 @@ pub struct App {
 }
 
 impl App {
+    fn settings(&self) -> Settings {
+        let mut settings = Settings {
+            squirrels: self.squirrels,
+            advantage: self.advantage,
+            loyalty: self.loyalty,
+            damage: self.damage,
+            board: self.board(),
+        };
+
+        for (card, _) in self.known_cards.iter().filter(|(_, enabled)| *enabled) {
+            if let Some(modifier) = card.modifier {
+                modifier.apply(&mut settings);
+            }
+        }
+
+        settings
+    }
+
+    fn board(&self) -> Board {
+        let mut token_replacements = vec![TokenReplacement::Doubler; self.token_doublers];
+        if self.chatterfang {
+            token_replacements.push(TokenReplacement::Chatterfang);
+        }
+
+        let mut damage_replacements = Vec::new();
+        if self.torbran {
+            damage_replacements.push(DamageReplacement::Torbran);
+        }
+        if self.fiery_emancipation {
+            damage_replacements.push(DamageReplacement::FieryEmancipation);
+        }
+
+        Board {
+            token_replacements,
+            damage_replacements,
+            damage_order: self.damage_order,
+            ..Board::default()
+        }
+    }
+
     fn update_results(&mut self, new_results: Vec<GameResult>) {
         for GameResult {
             outcome,
             damage,
             squirrels,
+            squirrel_damage,
             rolls,
             returns,
+            ..
         } in new_results.into_iter()
         {
             match outcome {
@@ -95,7 +163,7 @@ impl App {
             }
 
             self.results.total_damage += if self.squirrels {
-                damage + squirrels
+                damage + squirrel_damage
             } else {
                 damage
             };
@@ -109,7 +177,7 @@ impl App {
                 .entry(u32::min(
                     20,
                     if self.squirrels {
-                        damage + squirrels
+                        damage + squirrel_damage
                     } else {
                         damage
                     },
@@ -167,6 +235,12 @@ impl Component for App {
             advantage: 0,
             loyalty: 5,
             damage: 20,
+            token_doublers: 0,
+            chatterfang: false,
+            torbran: false,
+            fiery_emancipation: false,
+            damage_order: ReplacementOrder::LeastDamage,
+            known_cards: Vec::new(),
             progress: (0, 0),
             results: Results::default(),
             error_msg: None,
@@ -195,9 +269,43 @@ impl Component for App {
             Msg::ChangeDamage(damage_target) => {
                 self.damage = damage_target;
             }
+            Msg::ChangeTokenDoublers(token_doublers) => {
+                self.token_doublers = token_doublers;
+            }
             Msg::ToggleSquirrels => {
                 self.squirrels = !self.squirrels;
             }
+            Msg::ToggleChatterfang => {
+                self.chatterfang = !self.chatterfang;
+            }
+            Msg::ToggleTorbran => {
+                self.torbran = !self.torbran;
+            }
+            Msg::ToggleFieryEmancipation => {
+                self.fiery_emancipation = !self.fiery_emancipation;
+            }
+            Msg::ChangeDamageOrder(damage_order) => {
+                self.damage_order = damage_order;
+            }
+            Msg::ChangeDecklist(decklist) => {
+                match decklist.parse::<Decklist>() {
+                    Ok(decklist) => {
+                        self.error_msg = None;
+                        self.known_cards = decklist
+                            .known_cards()
+                            .into_iter()
+                            .map(|card| (card, true))
+                            .collect();
+                    }
+                    Err(err) if !decklist.trim().is_empty() => self.error_msg = Some(err),
+                    Err(_) => self.known_cards.clear(),
+                }
+            }
+            Msg::ToggleKnownCard(index) => {
+                if let Some((_, enabled)) = self.known_cards.get_mut(index) {
+                    *enabled = !*enabled;
+                }
+            }
             Msg::BeginSimulation => {
                 self.is_busy = true;
                 self.error_msg = None;
@@ -205,10 +313,7 @@ impl Component for App {
 
                 self.worker.send(Cmd::Begin {
                     simulations: self.simulations,
-                    squirrels: self.squirrels,
-                    advantage: self.advantage,
-                    loyalty: self.loyalty,
-                    damage: self.damage,
+                    settings: Box::new(self.settings()),
                 });
             }
             Msg::CancelSimulation => {
@@ -311,11 +416,78 @@ impl Component for App {
                                     />
                                 </label>
 
+                                <label class="label" for="token-doublers">
+                                    {"Token doubling effects:"}
+                                    <input class="input is-info" type="number" id="token-doublers" step="1" min="0" value={self.token_doublers.to_string()}
+                                        onchange={link.batch_callback(move |e: Event| {
+                                            let target: Option<EventTarget> = e.target();
+                                            let select = target.and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+                                            select.map(|select| {
+                                                let count = select.value();
+                                                Msg::ChangeTokenDoublers(count.parse().unwrap_or(0))
+                                            })
+                                        })}
+                                    />
+                                </label>
+
                                 <label for="squirrels">
                                     <input type="checkbox" id="squirrels" checked={self.squirrels} onchange={link.callback(|_| Msg::ToggleSquirrels)}/>
                                     {"Include damage from squirrels"}
                                 </label>
 
+                                <label for="chatterfang">
+                                    <input type="checkbox" id="chatterfang" checked={self.chatterfang} onchange={link.callback(|_| Msg::ToggleChatterfang)}/>
+                                    {"Chatterfang, Squirrel General"}
+                                </label>
+
+                                <label for="torbran">
+                                    <input type="checkbox" id="torbran" checked={self.torbran} onchange={link.callback(|_| Msg::ToggleTorbran)}/>
+                                    {"Torbran, Thane of Red Fell"}
+                                </label>
+
+                                <label for="fiery-emancipation">
+                                    <input type="checkbox" id="fiery-emancipation" checked={self.fiery_emancipation} onchange={link.callback(|_| Msg::ToggleFieryEmancipation)}/>
+                                    {"Fiery Emancipation"}
+                                </label>
+
+                                <label class="label" for="damage-order">
+                                    {"Damage replacement order:"}
+                                    <select id="damage-order"
+                                        onchange={link.batch_callback(move |e: Event| {
+                                            let target: Option<EventTarget> = e.target();
+                                            let select = target.and_then(|t| t.dyn_into::<HtmlSelectElement>().ok());
+                                            select.and_then(|select| select.value().parse().ok().map(Msg::ChangeDamageOrder))
+                                        })}
+                                    >
+                                        <option value="least" selected={self.damage_order == ReplacementOrder::LeastDamage}>{"Least damage, chosen by the opponent"}</option>
+                                        <option value="most" selected={self.damage_order == ReplacementOrder::MostDamage}>{"Most damage"}</option>
+                                        <option value="listed" selected={self.damage_order == ReplacementOrder::Listed}>{"Torbran first"}</option>
+                                    </select>
+                                </label>
+
+                                <label class="label" for="decklist">
+                                    {"Decklist (MTGA or MTGO):"}
+                                    <textarea id="decklist" rows="4" placeholder="4 Comet, Stellar Pup"
+                                        onchange={link.batch_callback(move |e: Event| {
+                                            let target: Option<EventTarget> = e.target();
+                                            let textarea = target.and_then(|t| t.dyn_into::<HtmlTextAreaElement>().ok());
+                                            textarea.map(|textarea| Msg::ChangeDecklist(textarea.value()))
+                                        })}
+                                    />
+                                </label>
+
+                                {
+                                    self.known_cards.iter().enumerate().map(|(index, (card, enabled))| {
+                                        html! {
+                                            <label for={format!("known-card-{index}")}>
+                                                <input type="checkbox" id={format!("known-card-{index}")} checked={*enabled}
+                                                    onchange={link.callback(move |_| Msg::ToggleKnownCard(index))}/>
+                                                {format!("{} ({})", card.name, card.modifier.map(|modifier| modifier.to_string()).unwrap_or_default())}
+                                            </label>
+                                        }
+                                    }).collect::<Html>()
+                                }
+
                                 <div class="buttons">
                                     <div class={if is_ready { "primary" } else { "primary outline" }}
                                         type="submit"
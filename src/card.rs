@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// What a card does for the rest of the chain once it is on the battlefield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardEffect {
+    /// If you would roll one or more dice, roll that many plus one and ignore the lowest roll
+    RollAdvantage,
+    /// Whenever you roll one or more dice, deal this much damage to each opponent
+    DamageOnRoll(u32),
+    /// Nothing that matters for Comet's activations
+    None,
+}
+
+/// A card in the graveyard or hand that can be cast during the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Card {
+    pub name: String,
+    pub mana_value: u32,
+    pub effect: CardEffect,
+}
+
+impl Card {
+    pub fn new(name: &str, mana_value: u32, effect: CardEffect) -> Self {
+        Self {
+            name: name.to_owned(),
+            mana_value,
+            effect,
+        }
+    }
+
+    pub fn pixie_guide() -> Self {
+        Card::new("Pixie Guide", 2, CardEffect::RollAdvantage)
+    }
+
+    pub fn barbarian_class() -> Self {
+        Card::new("Barbarian Class", 1, CardEffect::RollAdvantage)
+    }
+
+    pub fn wyll() -> Self {
+        Card::new("Wyll, Blade of Frontiers", 2, CardEffect::RollAdvantage)
+    }
+
+    pub fn brazen_dwarf() -> Self {
+        Card::new("Brazen Dwarf", 2, CardEffect::DamageOnRoll(1))
+    }
+
+    /// How much the card is worth to the rest of the chain, higher is better.
+    pub fn value(&self) -> u32 {
+        match self.effect {
+            CardEffect::RollAdvantage => 2,
+            CardEffect::DamageOnRoll(_) => 1,
+            CardEffect::None => 0,
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pixie-guide" => Ok(Card::pixie_guide()),
+            "barbarian-class" => Ok(Card::barbarian_class()),
+            "wyll" => Ok(Card::wyll()),
+            "brazen-dwarf" => Ok(Card::brazen_dwarf()),
+            "other" => Ok(Card::new("Other card", 2, CardEffect::None)),
+            _ => Err(format!(
+                "unknown card \"{s}\", expected pixie-guide, barbarian-class, wyll, brazen-dwarf or other"
+            )),
+        }
+    }
+}
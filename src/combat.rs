@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// When Comet is activated relative to the combat step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatTiming {
+    /// Comet is activated in the first main phase, so the hasty squirrels from the chain attack
+    #[default]
+    BeforeCombat,
+    /// Comet is activated in the second main phase, so only the squirrels already on the
+    /// battlefield attack, into all of the blockers
+    AfterCombat,
+}
+
+impl FromStr for CombatTiming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "before" => Ok(CombatTiming::BeforeCombat),
+            "after" => Ok(CombatTiming::AfterCombat),
+            _ => Err(format!("unknown combat timing \"{s}\", expected before or after")),
+        }
+    }
+}
+
+/// Result of the squirrels attacking the opponent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combat {
+    pub attackers: u32,
+    pub blocked: u32,
+    pub damage: u32,
+}
+
+/// Attacks with identical squirrels into the untapped blockers.
+///
+/// Every squirrel is the same, so the best the opponent can do is to block a different squirrel
+/// with each blocker. The power of the squirrels is the combat damage each of them deals.
+pub fn resolve(attackers: u32, power: u32, blockers: usize) -> Combat {
+    let blocked = u32::min(attackers, blockers as u32);
+
+    Combat {
+        attackers,
+        blocked,
+        damage: (attackers - blocked).saturating_mul(power),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_blocker_stops_a_squirrel() {
+        assert_eq!(
+            resolve(5, 2, 1),
+            Combat {
+                attackers: 5,
+                blocked: 1,
+                damage: 8
+            }
+        );
+        assert_eq!(resolve(3, 2, 5).damage, 0);
+        assert_eq!(resolve(0, 1, 0), Combat::default());
+    }
+}
@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::game::{
+    CopyEffect, DamageReplacement, PaidActivation, SacrificeOutlet, TokenReplacement,
+    SPARK_DOUBLE_LOYALTY,
+};
+use crate::simulator::Settings;
+
+const REGISTRY: &str = include_str!("../cards/registry.json");
+
+/// How a known card modifies the board once it is on the battlefield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Modifier {
+    /// Rolls one more die and ignores the lowest roll, like Pixie Guide
+    RollAdvantage,
+    /// Deals damage to each opponent whenever you roll dice, like Brazen Dwarf
+    DamageOnRoll(u32),
+    /// Creates twice as many tokens, like Anointed Procession
+    TokenDoubler,
+    /// Creates twice as many creature tokens, like Parallel Lives
+    CreatureTokenDoubler,
+    /// Chatterfang, Squirrel General
+    Chatterfang,
+    /// Torbran, Thane of Red Fell
+    Torbran,
+    /// Deals double damage, like Dictate of the Twin Gods
+    DamageDoubler,
+    /// Fiery Emancipation
+    FieryEmancipation,
+    /// Allows more loyalty activations each turn, like Oath of Teferi
+    ExtraActivations(u32),
+    /// The Chain Veil
+    ChainVeil,
+    /// Rings of Brighthearth
+    RingsOfBrighthearth,
+    /// Spark Double copying Comet
+    SparkDouble,
+    /// Gives the Squirrels +1/+1, like Deep Forest Hermit
+    SquirrelAnthem(u32),
+    /// Sacrifices the Squirrels, like Goblin Bombardment
+    SacrificeOutlet(SacrificeOutlet),
+}
+
+impl Modifier {
+    /// Adds the modifier to the settings, on top of what they already have.
+    pub fn apply(&self, settings: &mut Settings) {
+        let board = &mut settings.board;
+
+        match *self {
+            Modifier::RollAdvantage => settings.advantage += 1,
+            Modifier::DamageOnRoll(damage) => board.damage_on_roll += damage,
+            Modifier::TokenDoubler => board.token_replacements.push(TokenReplacement::Doubler),
+            Modifier::CreatureTokenDoubler => board
+                .token_replacements
+                .push(TokenReplacement::CreatureDoubler),
+            Modifier::Chatterfang => board.token_replacements.push(TokenReplacement::Chatterfang),
+            Modifier::Torbran => board.damage_replacements.push(DamageReplacement::Torbran),
+            Modifier::DamageDoubler => board.damage_replacements.push(DamageReplacement::Doubler),
+            Modifier::FieryEmancipation => board
+                .damage_replacements
+                .push(DamageReplacement::FieryEmancipation),
+            Modifier::ExtraActivations(activations) => board.extra_activations += activations,
+            Modifier::ChainVeil => board.paid_activations.push(PaidActivation::CHAIN_VEIL),
+            Modifier::RingsOfBrighthearth => {
+                board.copy_effects.push(CopyEffect::RINGS_OF_BRIGHTHEARTH)
+            }
+            Modifier::SparkDouble => board.extra_comets.push(SPARK_DOUBLE_LOYALTY),
+            Modifier::SquirrelAnthem(power) => board.squirrel_anthems += power,
+            Modifier::SacrificeOutlet(outlet) => board.sacrifice_outlets.push(outlet),
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Modifier::RollAdvantage => write!(f, "roll advantage"),
+            Modifier::DamageOnRoll(damage) => write!(f, "{damage} damage on roll"),
+            Modifier::TokenDoubler => write!(f, "token doubler"),
+            Modifier::CreatureTokenDoubler => write!(f, "creature token doubler"),
+            Modifier::Chatterfang => write!(f, "Chatterfang"),
+            Modifier::Torbran => write!(f, "Torbran"),
+            Modifier::DamageDoubler => write!(f, "damage doubler"),
+            Modifier::FieryEmancipation => write!(f, "Fiery Emancipation"),
+            Modifier::ExtraActivations(activations) => write!(f, "{activations} extra activations"),
+            Modifier::ChainVeil => write!(f, "paid activation"),
+            Modifier::RingsOfBrighthearth => write!(f, "copy effect"),
+            Modifier::SparkDouble => write!(f, "extra Comet"),
+            Modifier::SquirrelAnthem(power) => write!(f, "+{power}/+{power} for Squirrels"),
+            Modifier::SacrificeOutlet(_) => write!(f, "sacrifice outlet"),
+        }
+    }
+}
+
+/// A card the simulator knows, from the bundled registry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownCard {
+    pub name: String,
+    pub mana_value: u32,
+    /// How the card modifies the board, none for Comet and the lands
+    #[serde(default)]
+    pub modifier: Option<Modifier>,
+    /// Is the card a land tapping for one mana
+    #[serde(default)]
+    pub land: bool,
+}
+
+impl KnownCard {
+    /// Every card in the bundled registry.
+    pub fn registry() -> &'static [KnownCard] {
+        static CARDS: OnceLock<Vec<KnownCard>> = OnceLock::new();
+        CARDS.get_or_init(|| serde_json::from_str(REGISTRY).expect("invalid bundled card registry"))
+    }
+
+    /// The card with this name in the registry, ignoring case.
+    pub fn find(name: &str) -> Option<&'static KnownCard> {
+        KnownCard::registry()
+            .iter()
+            .find(|card| card.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Is this the card's full name or the short name before the comma, ignoring case.
+    pub fn is_named(&self, name: &str) -> bool {
+        let short_name = self.name.split(',').next().unwrap_or_default();
+        self.name.eq_ignore_ascii_case(name) || short_name.eq_ignore_ascii_case(name)
+    }
+}
+
+/// A line of the decklist, like `4 Brazen Dwarf`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub count: u32,
+    pub name: String,
+    /// Is the line under a lands header like `Lands (24)` or `// Lands`
+    #[serde(default)]
+    pub land: bool,
+}
+
+/// A decklist in the MTGA or MTGO text format.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decklist {
+    /// The commander and the companion, starting outside the library
+    pub commander: Vec<Entry>,
+    pub main: Vec<Entry>,
+    pub sideboard: Vec<Entry>,
+}
+
+impl Decklist {
+    /// The cards of the deck modifying the board, once each in the order of the decklist.
+    pub fn known_cards(&self) -> Vec<&'static KnownCard> {
+        let mut known: Vec<&'static KnownCard> = Vec::new();
+
+        let cards = self
+            .commander
+            .iter()
+            .chain(self.main.iter())
+            .filter_map(|entry| KnownCard::find(&entry.name))
+            .filter(|card| card.modifier.is_some());
+
+        for card in cards {
+            if !known.contains(&card) {
+                known.push(card);
+            }
+        }
+
+        known
+    }
+
+    /// Number of cards in the deck, including the commander.
+    pub fn size(&self) -> u32 {
+        self.commander
+            .iter()
+            .chain(self.main.iter())
+            .map(|entry| entry.count)
+            .sum()
+    }
+}
+
+impl FromStr for Decklist {
+    type Err = String;
+
+    /// Parses the MTGA export with its `Deck` and `Sideboard` headers and set codes, the MTGO
+    /// export with a blank line before the sideboard, or an export grouped by `// Creatures` like
+    /// headers.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut decklist = Decklist::default();
+        let mut in_sideboard = false;
+        let mut in_commander = false;
+        let mut has_headers = false;
+        let mut in_about = false;
+        let mut in_lands = false;
+
+        for line in s.lines().map(str::trim) {
+            // Exports grouping the cards by type have a header before the lands
+            if is_lands_header(line) {
+                has_headers = true;
+                in_lands = true;
+                continue;
+            }
+
+            match line.to_lowercase().as_str() {
+                "deck" => {
+                    has_headers = true;
+                    (in_sideboard, in_commander, in_about, in_lands) = (false, false, false, false);
+                    continue;
+                }
+                "commander" | "companion" => {
+                    has_headers = true;
+                    (in_sideboard, in_commander, in_about, in_lands) = (false, true, false, false);
+                    continue;
+                }
+                "sideboard" => {
+                    has_headers = true;
+                    (in_sideboard, in_commander, in_about, in_lands) = (true, false, false, false);
+                    continue;
+                }
+                // The newer MTGA export starts with the name of the deck
+                "about" => {
+                    has_headers = true;
+                    (in_sideboard, in_commander, in_about, in_lands) = (false, false, true, false);
+                    continue;
+                }
+                _ if in_about => continue,
+                "" => {
+                    // Without headers the sideboard follows the first blank line
+                    if !has_headers && !decklist.main.is_empty() {
+                        in_sideboard = true;
+                    }
+                    continue;
+                }
+                // Any other header, like `// Creatures`, ends the lands
+                _ if line.starts_with("//") || line.starts_with('#') => {
+                    has_headers = true;
+                    in_lands = false;
+                    // Grouped exports also mark the commander and the sideboard this way
+                    let header = line.trim_start_matches(['/', '#']).trim().to_lowercase();
+                    (in_sideboard, in_commander) = match header.as_str() {
+                        "sideboard" | "maybeboard" => (true, false),
+                        "commander" | "companion" => (false, true),
+                        _ => (false, false),
+                    };
+                    continue;
+                }
+                _ => {}
+            }
+
+            // The MTGO .dek style marks each sideboard line instead
+            let (line, marked_sideboard) = match line.strip_prefix("SB:") {
+                Some(line) => (line.trim_start(), true),
+                None => (line, false),
+            };
+            let mut entry = entry(line)?;
+            entry.land = in_lands;
+
+            if in_sideboard || marked_sideboard {
+                decklist.sideboard.push(entry);
+            } else if in_commander {
+                decklist.commander.push(entry);
+            } else {
+                decklist.main.push(entry);
+            }
+        }
+
+        if decklist.main.is_empty() && decklist.commander.is_empty() {
+            return Err("invalid decklist: no cards in the main deck".to_owned());
+        }
+
+        Ok(decklist)
+    }
+}
+
+/// Parses a line like `4 Brazen Dwarf`, `4x Brazen Dwarf` or `1 Brazen Dwarf (AFR) 128`.
+fn entry(line: &str) -> Result<Entry, String> {
+    let (count, name) = line
+        .split_once(' ')
+        .ok_or_else(|| format!("invalid decklist line \"{line}\", expected a count and a name"))?;
+
+    let count = count
+        .trim_end_matches(['x', 'X'])
+        .parse()
+        .map_err(|err| format!("invalid count in decklist line \"{line}\": {err}"))?;
+
+    // The MTGA export ends with the set code in parentheses and the collector number
+    let name = match name.rsplit_once(" (") {
+        Some((card, set)) if set.split_once(')').is_some() => card,
+        _ => name,
+    };
+
+    Ok(Entry {
+        count,
+        name: name.trim().to_owned(),
+        land: false,
+    })
+}
+
+/// Is the line a header like `Lands`, `Lands (24)` or `// Lands`.
+fn is_lands_header(line: &str) -> bool {
+    let header = line.trim_start_matches(['/', '#']).trim();
+    let header = match header.split_once('(') {
+        Some((header, count)) if count.trim_end_matches(')').trim().parse::<u32>().is_ok() => header,
+        _ => header,
+    };
+
+    matches!(header.trim().to_lowercase().as_str(), "land" | "lands")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(count: u32, name: &str) -> Entry {
+        Entry {
+            count,
+            name: name.to_owned(),
+            land: false,
+        }
+    }
+
+    #[test]
+    fn entry_parses_counts_and_set_codes() {
+        assert_eq!(entry("4 Brazen Dwarf").unwrap(), card(4, "Brazen Dwarf"));
+        assert_eq!(entry("4x Brazen Dwarf").unwrap(), card(4, "Brazen Dwarf"));
+        assert_eq!(entry("1 Brazen Dwarf (AFR) 128").unwrap(), card(1, "Brazen Dwarf"));
+        assert_eq!(
+            entry("1 Comet, Stellar Pup (UNF) 161").unwrap(),
+            card(1, "Comet, Stellar Pup")
+        );
+    }
+
+    #[test]
+    fn entry_rejects_lines_without_a_count() {
+        assert!(entry("Brazen").is_err());
+        assert!(entry("Brazen Dwarf").is_err());
+    }
+
+    #[test]
+    fn parses_the_mtga_export() {
+        let decklist: Decklist = "About\nName Comet\n\nCommander\n1 Comet, Stellar Pup (UNF) 161\n\nDeck\n4 Pixie Guide (AFR) 66\n\nSideboard\n2 Viscera Seer (M11) 120\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(decklist.commander, vec![card(1, "Comet, Stellar Pup")]);
+        assert_eq!(decklist.main, vec![card(4, "Pixie Guide")]);
+        assert_eq!(decklist.sideboard, vec![card(2, "Viscera Seer")]);
+        assert_eq!(decklist.size(), 5);
+    }
+
+    #[test]
+    fn parses_the_mtgo_export() {
+        let decklist: Decklist = "4 Comet, Stellar Pup\n20 Mountain\n\n2 Viscera Seer\nSB: 1 Torbran, Thane of Red Fell\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(decklist.main, vec![card(4, "Comet, Stellar Pup"), card(20, "Mountain")]);
+        assert_eq!(
+            decklist.sideboard,
+            vec![card(2, "Viscera Seer"), card(1, "Torbran, Thane of Red Fell")]
+        );
+    }
+
+    #[test]
+    fn marks_the_lands_section() {
+        let decklist: Decklist = "// Lands\n10 Arid Mesa\nLands (2)\n2 Gemstone Caverns\n// Creatures\n4 Pixie Guide\n"
+            .parse()
+            .unwrap();
+
+        let lands: Vec<bool> = decklist.main.iter().map(|entry| entry.land).collect();
+        assert_eq!(lands, vec![true, true, false]);
+    }
+
+    #[test]
+    fn keeps_groups_after_blank_lines_in_the_main_deck() {
+        let decklist: Decklist = "// Commander\n1 Comet, Stellar Pup\n\n// Creatures\n4 Pixie Guide\n\n// Enchantments\n1 Parallel Lives\n\n// Lands\n24 Mountain\n\n// Sideboard\n2 Viscera Seer\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(decklist.commander, vec![card(1, "Comet, Stellar Pup")]);
+        assert_eq!(
+            decklist.main,
+            vec![
+                card(4, "Pixie Guide"),
+                card(1, "Parallel Lives"),
+                Entry {
+                    land: true,
+                    ..card(24, "Mountain")
+                }
+            ]
+        );
+        assert_eq!(decklist.sideboard, vec![card(2, "Viscera Seer")]);
+    }
+
+    #[test]
+    fn rejects_an_empty_decklist() {
+        assert!("".parse::<Decklist>().is_err());
+        assert!("Sideboard\n2 Viscera Seer\n".parse::<Decklist>().is_err());
+        assert!("4 Brazen Dwarf\nnot a card\n".parse::<Decklist>().is_err());
+    }
+
+    #[test]
+    fn finds_the_known_cards_once() {
+        let decklist: Decklist = "4 Pixie Guide\n20 Mountain\n1 pixie guide\n1 Lightning Bolt\n"
+            .parse()
+            .unwrap();
+
+        let names: Vec<&str> = decklist.known_cards().iter().map(|card| card.name.as_str()).collect();
+        assert_eq!(names, vec!["Pixie Guide"]);
+    }
+
+    #[test]
+    fn matches_the_short_name() {
+        let torbran = KnownCard::find("torbran, thane of red fell").unwrap();
+        assert!(torbran.is_named("Torbran"));
+        assert!(!torbran.is_named("Thane of Red Fell"));
+    }
+}
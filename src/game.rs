@@ -1,5 +1,15 @@
-use rand::{distributions::Uniform, Rng};
+use rand::{distributions::Uniform, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use serde::{Serialize, Deserialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::ability::{Ability, Effect};
+use crate::attraction::{Attraction, AttractionDeck, VISIT_DIE};
+use crate::card::{Card, CardEffect};
+use crate::combat::{self, Combat, CombatTiming};
+use crate::interaction::{Answer, Interaction};
+use crate::script::{self, Actions, Script, ScriptGame};
+use crate::strategy::{DamageTarget, DefaultStrategy, Strategy};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Outcome {
@@ -7,51 +17,684 @@ pub enum Outcome {
     Lose,
 }
 
+/// Power of a single 1/1 green Squirrel creature token.
+const SQUIRREL_POWER: u32 = 1;
+/// Loyalty of Spark Double copying Comet: the printed 5 plus the counter Spark Double adds.
+pub const SPARK_DOUBLE_LOYALTY: i32 = 6;
+/// Damage after which a chain is considered infinite and the activations stop.
+const MAX_DAMAGE: u32 = 10000;
+/// Activations after which a chain is considered infinite, for abilities looping without damage.
+const MAX_ACTIVATIONS: usize = 10000;
+/// Roll advantage the scripts can build up over a game, so that the rolls stay quick.
+const MAX_SCRIPT_ROLL_ADVANTAGE: usize = 100;
+
+/// Replacement effects applied when tokens would be created under your control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenReplacement {
+    /// Anointed Procession, Doubling Season, Mondrak: twice that many tokens are created instead.
+    Doubler,
+    /// Parallel Lives: twice that many creature tokens are created instead.
+    CreatureDoubler,
+    /// Chatterfang, Squirrel General: those tokens plus that many 1/1 green Squirrel creature tokens are created instead.
+    Chatterfang,
+}
+
+impl TokenReplacement {
+    /// Replaces the tokens being created and the Squirrels Chatterfang added to them, the
+    /// Squirrels being creatures whatever the tokens are.
+    fn apply(&self, tokens: u32, squirrels: u32, creatures: bool) -> (u32, u32) {
+        match self {
+            TokenReplacement::Doubler => (tokens.saturating_mul(2), squirrels.saturating_mul(2)),
+            TokenReplacement::CreatureDoubler if creatures => {
+                (tokens.saturating_mul(2), squirrels.saturating_mul(2))
+            }
+            TokenReplacement::CreatureDoubler => (tokens, squirrels.saturating_mul(2)),
+            TokenReplacement::Chatterfang => (tokens, squirrels.saturating_add(tokens)),
+        }
+    }
+}
+
+/// Replacement effects applied when a source you control would deal damage to an opponent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageReplacement {
+    /// Torbran, Thane of Red Fell: a red source you control deals that much damage plus 2 instead.
+    Torbran,
+    /// Dictate of the Twin Gods, Furnace of Rath: a source deals double that damage instead.
+    Doubler,
+    /// Fiery Emancipation: a source you control deals triple that damage instead.
+    FieryEmancipation,
+}
+
+impl DamageReplacement {
+    fn apply(&self, damage: u32, red: bool) -> u32 {
+        match self {
+            DamageReplacement::Torbran if red => damage.saturating_add(2),
+            DamageReplacement::Torbran => damage,
+            DamageReplacement::Doubler => damage.saturating_mul(2),
+            DamageReplacement::FieryEmancipation => damage.saturating_mul(3),
+        }
+    }
+
+    /// Does the effect add to the damage rather than multiply it.
+    fn is_additive(&self) -> bool {
+        matches!(self, DamageReplacement::Torbran)
+    }
+}
+
+/// How the order of several applicable damage replacement effects is chosen.
+///
+/// Per the Torbran rulings the player being dealt damage chooses the order, so `LeastDamage`
+/// is the rules accurate choice against an opponent who knows what they are doing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplacementOrder {
+    /// Apply the effects in the order they are listed on the board
+    Listed,
+    /// Apply the effects in the order dealing the most damage
+    MostDamage,
+    /// Apply the effects in the order dealing the least damage
+    #[default]
+    LeastDamage,
+}
+
+impl FromStr for ReplacementOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "listed" => Ok(ReplacementOrder::Listed),
+            "most" => Ok(ReplacementOrder::MostDamage),
+            "least" => Ok(ReplacementOrder::LeastDamage),
+            _ => Err(format!("unknown replacement order \"{s}\", expected listed, most or least")),
+        }
+    }
+}
+
+/// A permanent letting you pay mana to activate a loyalty ability one more time this turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaidActivation {
+    /// Mana paid for each extra activation
+    pub cost: u32,
+    /// How many times it can be used each turn
+    pub uses: u32,
+}
+
+impl PaidActivation {
+    /// The Chain Veil: {4}, {T}: activate a loyalty ability once more this turn.
+    pub const CHAIN_VEIL: PaidActivation = PaidActivation { cost: 4, uses: 1 };
+}
+
+/// A permanent letting you pay mana to copy an activated ability, like Rings of Brighthearth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyEffect {
+    /// Mana paid for each copy
+    pub cost: u32,
+}
+
+impl CopyEffect {
+    /// Rings of Brighthearth: whenever you activate an ability, you may pay {2} to copy that ability.
+    pub const RINGS_OF_BRIGHTHEARTH: CopyEffect = CopyEffect { cost: 2 };
+}
+
+/// When to spend mana on copies of the ability instead of paid extra activations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ManaPolicy {
+    /// Keep enough mana for the paid extra activations and only copy with the rest
+    #[default]
+    ActivationsFirst,
+    /// Copy the ability whenever there is mana for it
+    CopiesFirst,
+}
+
+impl FromStr for ManaPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "activations" => Ok(ManaPolicy::ActivationsFirst),
+            "copies" => Ok(ManaPolicy::CopiesFirst),
+            _ => Err(format!("unknown mana policy \"{s}\", expected activations or copies")),
+        }
+    }
+}
+
+/// A permanent letting you sacrifice a creature for value, like Goblin Bombardment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SacrificeOutlet {
+    /// Damage dealt to the opponent for each sacrificed creature
+    pub damage: u32,
+    /// Cards scried for each sacrificed creature
+    pub scry: u32,
+    /// Is the outlet a red source
+    pub red: bool,
+}
+
+impl SacrificeOutlet {
+    /// Goblin Bombardment: Sacrifice a creature: It deals 1 damage to any target.
+    pub const GOBLIN_BOMBARDMENT: SacrificeOutlet = SacrificeOutlet {
+        damage: 1,
+        scry: 0,
+        red: true,
+    };
+
+    /// Viscera Seer: Sacrifice a creature: Scry 1.
+    pub const VISCERA_SEER: SacrificeOutlet = SacrificeOutlet {
+        damage: 0,
+        scry: 1,
+        red: false,
+    };
+}
+
+impl FromStr for SacrificeOutlet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let amount = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|err| format!("invalid sacrifice outlet amount \"{value}\": {err}"))
+        };
+
+        match s.split_once(':') {
+            Some(("damage", value)) => Ok(SacrificeOutlet {
+                damage: amount(value)?,
+                scry: 0,
+                red: false,
+            }),
+            Some(("scry", value)) => Ok(SacrificeOutlet {
+                damage: 0,
+                scry: amount(value)?,
+                red: false,
+            }),
+            _ => match s {
+                "goblin-bombardment" => Ok(SacrificeOutlet::GOBLIN_BOMBARDMENT),
+                "viscera-seer" => Ok(SacrificeOutlet::VISCERA_SEER),
+                _ => Err(format!(
+                    "unknown sacrifice outlet \"{s}\", expected goblin-bombardment, viscera-seer, damage:<N> or scry:<N>"
+                )),
+            },
+        }
+    }
+}
+
+/// Permanents on the battlefield modifying the results of Comet's activations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Board {
+    /// The die rolling ability being activated, Comet's by default
+    pub ability: Ability,
+
+    /// Token replacement effects, applied in the order creating the most tokens
+    pub token_replacements: Vec<TokenReplacement>,
+
+    /// Damage replacement effects
+    pub damage_replacements: Vec<DamageReplacement>,
+
+    /// Who chooses the order of the damage replacement effects
+    pub damage_order: ReplacementOrder,
+
+    /// Additional loyalty activations each turn, like from Oath of Teferi
+    pub extra_activations: u32,
+
+    /// Starting loyalty of each nonlegendary copy of Comet, like from Spark Double
+    pub extra_comets: Vec<i32>,
+
+    /// Mana costed extra activations, like The Chain Veil
+    pub paid_activations: Vec<PaidActivation>,
+
+    /// Ability copying effects, like Rings of Brighthearth
+    pub copy_effects: Vec<CopyEffect>,
+
+    /// How the mana is split between copies and paid activations
+    pub mana_policy: ManaPolicy,
+
+    /// Damage dealt to each opponent whenever you roll one or more dice, like from Brazen Dwarf
+    pub damage_on_roll: u32,
+
+    /// Cards in graveyard that can be returned to hand, in no particular order
+    pub graveyard: Vec<Card>,
+
+    /// Toughness of each untapped creature the opponent can block squirrels with
+    pub blockers: Vec<u32>,
+
+    /// Squirrels already on the battlefield that can attack this turn
+    pub squirrels: u32,
+
+    /// +1/+1 effects for Squirrels, like Deep Forest Hermit
+    pub squirrel_anthems: u32,
+
+    /// Is Comet activated before or after combat
+    pub combat_timing: CombatTiming,
+
+    /// Sacrifice outlets the squirrels can be sacrificed to
+    pub sacrifice_outlets: Vec<SacrificeOutlet>,
+
+    /// Life totals of each opponent, or empty for a single opponent at the target damage. The
+    /// blockers belong to the first opponent.
+    pub opponents: Vec<u32>,
+
+    /// How the opponent answers the activations
+    pub interaction: Interaction,
+
+    /// The Attraction deck and how many of its Attractions are open
+    pub attractions: AttractionDeck,
+
+    /// Scripts with custom card effects
+    pub scripts: Vec<Script>,
+
+    /// Mana available for paying costs during the turn
+    pub mana: u32,
+}
+
+/// A single Comet on the battlefield with its own loyalty and activations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comet {
+    pub loyalty: i32,
+    pub activations_left: u32,
+    pub extra_activations_left: u32,
+}
+
+impl Comet {
+    pub fn new(loyalty: i32, extra_activations: u32) -> Self {
+        Self {
+            loyalty,
+            activations_left: 1,
+            extra_activations_left: extra_activations,
+        }
+    }
+
+    /// Free activations left this turn, including the unused extra activations.
+    pub fn free_activations_left(&self) -> u32 {
+        self.activations_left + self.extra_activations_left
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GameResult {
     pub outcome: Outcome,
     pub damage: u32,
     pub squirrels: u32,
+    pub squirrel_damage: u32,
     pub rolls: usize,
     pub returns: usize,
+    pub extra_activations: usize,
+    pub paid_activations: usize,
+    pub copies: usize,
+    pub casts: usize,
+    pub loyalty: i32,
+    pub loyalties: Vec<i32>,
+    pub total_damage: u32,
+    pub blockers_killed: usize,
+    pub attackers: u32,
+    pub blocked: u32,
+    pub sacrificed: u32,
+    pub sacrifice_damage: u32,
+    pub scry: u32,
+    pub eliminated: usize,
+    pub life_left: Vec<u32>,
+    pub countered: usize,
+    pub removed: bool,
+    pub treasures: u32,
+    pub visits: Vec<u32>,
 }
 
 #[derive(Debug)]
 pub struct Game {
+    board: Board,
+    strategy: Arc<dyn Strategy>,
+    rng: StdRng,
     roll_advantage: usize,
     rolls: usize,
+    activations: usize,
     returns: usize,
     damage: u32,
     squirrels: u32,
     include_squirrels: bool,
-    activations_left: u32,
-    loyalty: i32,
+    comets: Vec<Comet>,
+    active: usize,
+    extra_activations_used: usize,
+    paid_activations_left: Vec<PaidActivation>,
+    paid_activations_used: usize,
+    copies: usize,
+    casts: usize,
+    graveyard: Vec<Card>,
+    hand: Vec<Card>,
+    damage_on_roll: u32,
+    blockers: Vec<u32>,
+    blockers_killed: usize,
+    sacrificed: u32,
+    sacrificed_before_combat: u32,
+    sacrifice_damage: u32,
+    scry: u32,
+    opponents: Vec<u32>,
+    opponent_damage: Vec<u32>,
+    /// The squirrel attack on each opponent, once combat has been resolved
+    resolved_attacks: Option<Vec<Combat>>,
+    held_answer: Option<Answer>,
+    countered: usize,
+    removed: bool,
+    treasures: u32,
+    attraction_deck: Vec<usize>,
+    open_attractions: Vec<usize>,
+    visits: Vec<u32>,
+    summoning_sick: u32,
+    mana: u32,
     target_dmg: u32,
 }
 
 impl Game {
     pub fn new(include_squirrels: bool, roll_advantage: usize, loyalty: i32, target_dmg: u32) -> Self {
         Self {
+            board: Board::default(),
+            strategy: Arc::new(DefaultStrategy),
+            rng: StdRng::from_rng(rand::thread_rng()).expect("failed to seed the dice"),
             roll_advantage,
-            activations_left: 1,
+            comets: vec![Comet::new(loyalty, 0)],
+            active: 0,
+            extra_activations_used: 0,
+            paid_activations_left: Vec::new(),
+            paid_activations_used: 0,
+            copies: 0,
+            casts: 0,
+            graveyard: Vec::new(),
+            hand: Vec::new(),
+            damage_on_roll: 0,
+            blockers: Vec::new(),
+            blockers_killed: 0,
+            sacrificed: 0,
+            sacrificed_before_combat: 0,
+            sacrifice_damage: 0,
+            scry: 0,
+            opponents: vec![target_dmg],
+            opponent_damage: vec![0],
+            resolved_attacks: None,
+            held_answer: None,
+            countered: 0,
+            removed: false,
+            treasures: 0,
+            attraction_deck: Vec::new(),
+            open_attractions: Vec::new(),
+            visits: Vec::new(),
+            summoning_sick: 0,
+            mana: 0,
             squirrels: 0,
             damage: 0,
             rolls: 0,
+            activations: 0,
             returns: 0,
-            loyalty,
             include_squirrels,
             target_dmg,
         }
     }
 
+    pub fn with_board(mut self, board: Board) -> Self {
+        let loyalty = self.comets[0].loyalty;
+        self.comets = std::iter::once(loyalty)
+            .chain(board.extra_comets.iter().copied())
+            .map(|loyalty| Comet::new(loyalty, board.extra_activations))
+            .collect();
+        self.paid_activations_left = board.paid_activations.clone();
+        self.graveyard = board.graveyard.clone();
+        self.damage_on_roll = board.damage_on_roll;
+        self.blockers = board.blockers.clone();
+        self.mana = board.mana;
+        self.held_answer = board.interaction.held;
+        self.attraction_deck = (0..board.attractions.attractions.len()).collect();
+        self.visits = vec![0; board.attractions.attractions.len()];
+        if !board.opponents.is_empty() {
+            self.opponents = board.opponents.clone();
+            self.opponent_damage = vec![0; board.opponents.len()];
+            self.target_dmg = board.opponents.iter().sum();
+        }
+        self.board = board;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: Arc<dyn Strategy>) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Seeds the dice, so that games with the same seed roll the same sequence of dice.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Every Comet on the battlefield, starting with the original one.
+    pub fn comets(&self) -> &[Comet] {
+        &self.comets
+    }
+
+    /// Index of the Comet being activated in `Game::comets`.
+    pub fn active_comet(&self) -> usize {
+        self.active
+    }
+
+    /// Loyalty of the Comet being activated.
+    pub fn loyalty(&self) -> i32 {
+        self.comets[self.active].loyalty
+    }
+
+    /// Activations left for the Comet being activated.
+    pub fn activations_left(&self) -> u32 {
+        self.comets[self.active].activations_left
+    }
+
+    fn comet_mut(&mut self) -> &mut Comet {
+        &mut self.comets[self.active]
+    }
+
+    pub fn roll_advantage(&self) -> usize {
+        self.roll_advantage
+    }
+
+    pub fn damage(&self) -> u32 {
+        self.damage
+    }
+
+    pub fn squirrels(&self) -> u32 {
+        self.squirrels
+    }
+
+    pub fn include_squirrels(&self) -> bool {
+        self.include_squirrels
+    }
+
+    /// Damage needed to eliminate every opponent.
+    pub fn target_damage(&self) -> u32 {
+        self.target_dmg
+    }
+
+    /// Starting life total of each opponent.
+    pub fn opponents(&self) -> &[u32] {
+        &self.opponents
+    }
+
+    /// Life each opponent has left, before the squirrel attack until combat has been resolved.
+    pub fn life_left(&self) -> Vec<u32> {
+        self.opponents
+            .iter()
+            .zip(&self.opponent_damage)
+            .map(|(life, damage)| life.saturating_sub(*damage))
+            .collect()
+    }
+
+    pub fn mana(&self) -> u32 {
+        self.mana
+    }
+
+    pub fn hand(&self) -> &[Card] {
+        &self.hand
+    }
+
+    /// Toughness left on each of the opponent's remaining blockers.
+    pub fn blockers(&self) -> &[u32] {
+        &self.blockers
+    }
+
+    /// Combat damage dealt by a single unblocked squirrel.
+    pub fn squirrel_power(&self) -> u32 {
+        self.replace_damage(SQUIRREL_POWER + self.board.squirrel_anthems, false)
+    }
+
+    /// Squirrels on the battlefield that haven't been sacrificed, or blocked and killed in combat.
+    pub fn squirrels_on_battlefield(&self) -> u32 {
+        let blocked: u32 = self.resolved_attacks.iter().flatten().map(|attack| attack.blocked).sum();
+
+        self.board
+            .squirrels
+            .saturating_add(self.squirrels)
+            .saturating_sub(self.sacrificed + blocked)
+    }
+
+    /// Squirrels attacking in the combat step. The squirrels without haste created at the
+    /// beginning of combat can't attack.
+    pub fn attackers(&self) -> u32 {
+        match self.board.combat_timing {
+            CombatTiming::BeforeCombat => self
+                .board
+                .squirrels
+                .saturating_add(self.squirrels)
+                .saturating_sub(self.sacrificed_before_combat.saturating_add(self.summoning_sick)),
+            CombatTiming::AfterCombat => self.board.squirrels,
+        }
+    }
+
+    /// Blockers the squirrels attack into. When Comet is activated after combat the blockers
+    /// destroyed by Comet were still around to block.
+    pub fn combat_blockers(&self) -> usize {
+        match self.board.combat_timing {
+            CombatTiming::BeforeCombat => self.blockers.len(),
+            CombatTiming::AfterCombat => self.board.blockers.len(),
+        }
+    }
+
+    /// The squirrel attack on each opponent, split between the opponents by the strategy. Once
+    /// combat has been resolved this is the attack that happened.
+    pub fn attacks(&self) -> Vec<Combat> {
+        if let Some(attacks) = &self.resolved_attacks {
+            return attacks.clone();
+        }
+
+        let mut attackers = self.attackers();
+        let assigned = self.strategy.attack(self, attackers);
+
+        (0..self.opponents.len())
+            .map(|index| {
+                let count = u32::min(assigned.get(index).copied().unwrap_or(0), attackers);
+                attackers -= count;
+
+                let blockers = if index == 0 { self.combat_blockers() } else { 0 };
+                combat::resolve(count, self.squirrel_power(), blockers)
+            })
+            .collect()
+    }
+
+    /// The squirrel attack with the current squirrels and blockers, over all of the opponents.
+    pub fn combat(&self) -> Combat {
+        self.attacks()
+            .into_iter()
+            .fold(Combat::default(), |total, attack| Combat {
+                attackers: total.attackers.saturating_add(attack.attackers),
+                blocked: total.blocked.saturating_add(attack.blocked),
+                damage: total.damage.saturating_add(attack.damage),
+            })
+    }
+
+    /// Combat damage dealt by the squirrel attack.
+    pub fn squirrel_damage(&self) -> u32 {
+        self.combat().damage
+    }
+
+    /// Damage dealt to each opponent so far, including the squirrel attack when squirrels are
+    /// included.
+    pub fn opponent_damage(&self) -> Vec<u32> {
+        // The resolved attack is already part of the damage dealt
+        if self.include_squirrels && self.resolved_attacks.is_none() {
+            self.opponent_damage
+                .iter()
+                .zip(self.attacks())
+                .map(|(damage, attack)| damage.saturating_add(attack.damage))
+                .collect()
+        } else {
+            self.opponent_damage.clone()
+        }
+    }
+
+    /// Number of opponents dealt at least their life total in damage.
+    pub fn eliminated(&self) -> usize {
+        self.opponent_damage()
+            .iter()
+            .zip(&self.opponents)
+            .filter(|(damage, life)| damage >= life)
+            .count()
+    }
+
+    /// Has enough damage been dealt to eliminate every opponent.
+    pub fn is_lethal(&self) -> bool {
+        self.eliminated() == self.opponents.len()
+    }
+
+    /// Damage dealt so far, including the squirrel attack when squirrels are included.
+    pub fn total_damage(&self) -> u32 {
+        if self.include_squirrels {
+            self.damage.saturating_add(self.squirrel_damage())
+        } else {
+            self.damage
+        }
+    }
+
+    /// Free activations left this turn for the Comet being activated, including the unused extra
+    /// activations.
+    pub fn free_activations_left(&self) -> u32 {
+        self.comets[self.active].free_activations_left()
+    }
+
+    pub fn damage_on_roll(&self) -> u32 {
+        self.damage_on_roll
+    }
+
+    /// The most loyalty a single resolution of the ability can lose.
+    pub fn worst_loyalty_change(&self) -> i32 {
+        self.board.ability.worst_loyalty_change()
+    }
+
     pub fn run(&mut self) -> GameResult {
-        while self.activations_left > 0 && self.loyalty > 0 && self.damage < 10000 {
-            self.activate()
+        let strategy = Arc::clone(&self.strategy);
+
+        self.attraction_deck.shuffle(&mut self.rng);
+        for _ in 0..self.board.attractions.open {
+            let opened = self.open_attraction();
+            log::debug!("{opened}");
+        }
+
+        // The Attractions are visited at the beginning of combat
+        if self.board.combat_timing == CombatTiming::AfterCombat {
+            self.visit_attractions();
         }
 
-        let outcome = if self.damage >= self.target_dmg
-            || self.include_squirrels && (self.damage + self.squirrels) >= self.target_dmg
+        while self.damage < MAX_DAMAGE
+            && self.activations < MAX_ACTIVATIONS
+            && self.select_comet()
+            && strategy.keep_activating(self)
+            && self.has_activation()
         {
+            self.activate()
+        }
+
+        if self.board.combat_timing == CombatTiming::BeforeCombat {
+            self.visit_attractions();
+        }
+
+        let combat = self.resolve_combat();
+
+        // After combat damage the rest of the squirrels have nothing better to do
+        self.sacrifice_squirrels(true);
+
+        let outcome = if self.is_lethal() {
             Outcome::Win
         } else {
             Outcome::Lose
@@ -61,76 +704,1160 @@ impl Game {
             outcome,
             damage: self.damage,
             squirrels: self.squirrels,
+            squirrel_damage: combat.damage,
             rolls: self.rolls,
             returns: self.returns,
+            extra_activations: self.extra_activations_used,
+            paid_activations: self.paid_activations_used,
+            copies: self.copies,
+            casts: self.casts,
+            loyalty: self.comets[0].loyalty,
+            loyalties: self.comets.iter().map(|comet| comet.loyalty).collect(),
+            total_damage: self.total_damage(),
+            blockers_killed: self.blockers_killed,
+            attackers: combat.attackers,
+            blocked: combat.blocked,
+            sacrificed: self.sacrificed,
+            sacrifice_damage: self.sacrifice_damage,
+            scry: self.scry,
+            eliminated: self.eliminated(),
+            life_left: self
+                .opponent_damage()
+                .iter()
+                .zip(&self.opponents)
+                .map(|(damage, life)| life.saturating_sub(*damage))
+                .collect(),
+            countered: self.countered,
+            removed: self.removed,
+            treasures: self.treasures,
+            visits: self.visits.clone(),
         }
     }
 
-    fn activate(&mut self) {
-        self.activations_left -= 1;
+    /// Attacks with the squirrels, so that what happens after combat can't change where the
+    /// attack went. The combat damage counts towards the opponents' life totals only when
+    /// squirrels are included.
+    fn resolve_combat(&mut self) -> Combat {
+        let attacks = self.attacks();
+        if self.include_squirrels {
+            for (damage, attack) in self.opponent_damage.iter_mut().zip(&attacks) {
+                *damage = damage.saturating_add(attack.damage);
+            }
+        }
 
-        let rolls_to_take = 1 + self.roll_advantage;
-        self.rolls += rolls_to_take;
+        self.resolved_attacks = Some(attacks);
+        self.combat()
+    }
+
+    /// Lets the strategy pick the next Comet to activate out of the ones that still could be.
+    fn select_comet(&mut self) -> bool {
+        let can_pay = self
+            .paid_activations_left
+            .iter()
+            .any(|paid| paid.uses > 0 && paid.cost <= self.mana);
+
+        let candidates: Vec<usize> = self
+            .comets
+            .iter()
+            .enumerate()
+            .filter(|(_, comet)| comet.loyalty > 0 && (comet.free_activations_left() > 0 || can_pay))
+            .map(|(index, _)| index)
+            .collect();
+
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let chosen = Arc::clone(&self.strategy).choose_comet(self, &candidates);
+        self.active = if candidates.contains(&chosen) {
+            chosen
+        } else {
+            candidates[0]
+        };
+
+        true
+    }
+
+    /// Checks if there is an activation left, using the free extra activations before
+    /// paying mana for the cheapest paid activation.
+    fn has_activation(&mut self) -> bool {
+        if self.activations_left() > 0 {
+            return true;
+        }
+
+        if self.comets[self.active].extra_activations_left > 0 {
+            self.comet_mut().extra_activations_left -= 1;
+            self.extra_activations_used += 1;
+            self.comet_mut().activations_left += 1;
+
+            log::debug!(
+                "[Loyalty: {}][Activations: {}] Extra activation for this turn.",
+                self.loyalty(),
+                self.activations_left()
+            );
+
+            return true;
+        }
+
+        let cheapest = self
+            .paid_activations_left
+            .iter()
+            .enumerate()
+            .filter(|(_, paid)| paid.uses > 0 && paid.cost <= self.mana)
+            .min_by_key(|(_, paid)| paid.cost)
+            .map(|(index, paid)| (index, paid.cost));
+
+        if let Some((index, cost)) = cheapest {
+            if !Arc::clone(&self.strategy).pay_for_activation(self, cost) {
+                return false;
+            }
+
+            self.paid_activations_left[index].uses -= 1;
+            self.mana -= cost;
+            self.paid_activations_used += 1;
+
+            // The Chain Veil lets every planeswalker activate once more
+            for comet in self.comets.iter_mut().filter(|comet| comet.loyalty > 0) {
+                comet.activations_left += 1;
+            }
+
+            log::debug!(
+                "[Loyalty: {}][Activations: {}] Paid {} mana for an extra activation.",
+                self.loyalty(),
+                self.activations_left(),
+                cost
+            );
+
+            return true;
+        }
+
+        false
+    }
+
+    fn activate(&mut self) {
+        self.comet_mut().activations_left -= 1;
+        self.activations += 1;
 
         log::debug!(
-            "[Loyalty: {}][Activations: {}] 0: Roll a six-sided die.",
-            self.loyalty,
-            self.activations_left
+            "[Loyalty: {}][Activations: {}] 0: Roll a {}-sided die.",
+            self.loyalty(),
+            self.activations_left(),
+            self.board.ability.die
         );
 
-        let mut rng = rand::thread_rng();
-        let die_range = Uniform::new_inclusive::<u32, u32>(1, 6);
-        let max_roll = (&mut rng)
-            .sample_iter(die_range)
-            .take(rolls_to_take)
-            .inspect(|roll| log::debug!("Rolled a {roll}."))
-            .max()
-            .unwrap_or(0);
+        self.trigger("on_activate", ());
 
-        log::debug!("Kept the max roll of {max_roll}.");
+        // Each copy goes on the stack above the original ability and resolves separately
+        let copies = self.copy_ability();
+        let response = self.respond();
+        for _ in 0..copies {
+            self.resolve();
+        }
 
-        // 1 or 2 — [+2], then create two 1/1 green Squirrel creature tokens. They gain haste until end of turn.
-        if max_roll == 1 || max_roll == 2 {
-            self.loyalty += 2;
-            self.squirrels += 2;
+        if response == Some(Answer::Counter) {
+            self.countered += 1;
             log::debug!(
-                "[Loyalty: {}][Activations: {}] +2: Create two 1/1 green Squirrel creature tokens.",
-                self.loyalty,
-                self.activations_left
+                "[Loyalty: {}][Activations: {}] The opponent countered the activation.",
+                self.loyalty(),
+                self.activations_left()
             );
+        } else {
+            self.resolve();
+        }
 
-        // 3 — [-1], then return a card with mana value 2 or less from your graveyard to your hand.
-        } else if max_roll == 3 {
-            self.loyalty -= 1;
-            self.returns += 1;
-            log::debug!(
-                "[Loyalty: {}][Activations: {}] -1: No action. (\"Return a card with mana value 2 or less from your graveyard to your hand.\")",
-                self.loyalty,
-                self.activations_left
+        // The abilities on the stack resolve even though Comet is gone
+        if response == Some(Answer::Removal) {
+            self.removed = true;
+            self.comet_mut().loyalty = 0;
+            log::debug!("The opponent removed Comet in response to the activation.");
+        }
+
+        // With the stack empty again the returned cards can be cast before activating again
+        self.cast_from_hand();
+        self.sacrifice_squirrels(false);
+    }
+
+    /// Sacrifices the squirrels the strategy wants to each of the sacrifice outlets.
+    fn sacrifice_squirrels(&mut self, after_combat: bool) {
+        let strategy = Arc::clone(&self.strategy);
+
+        for outlet in self.board.sacrifice_outlets.clone() {
+            let count = u32::min(
+                strategy.sacrifice(self, &outlet, after_combat),
+                self.squirrels_on_battlefield(),
             );
+            if count == 0 {
+                continue;
+            }
 
-        //4 or 5 — Comet, Stellar Pup deals damage equal to the number of loyalty counters on him to a creature or player, then [-2].
-        } else if max_roll == 4 || max_roll == 5 {
-            self.damage += i32::max(self.loyalty, 0) as u32;
-            self.loyalty -= 2;
+            let dealt = count.saturating_mul(self.replace_damage(outlet.damage, outlet.red));
+            self.damage_opponent(strategy.opponent_target(self, dealt), dealt);
+            self.sacrifice_damage = self.sacrifice_damage.saturating_add(dealt);
+            self.scry = self.scry.saturating_add(count.saturating_mul(outlet.scry));
+            self.sacrificed += count;
+            if !after_combat {
+                self.sacrificed_before_combat += count;
+            }
 
             log::debug!(
-                "[Loyalty: {}][Activations: {}] -2: Comet, Stellar Pup deals {} damage.",
-                self.loyalty,
-                self.activations_left,
-                self.loyalty + 2
+                "[Loyalty: {}][Activations: {}] Sacrificed {} squirrels for {} damage and scry {}.",
+                self.loyalty(),
+                self.activations_left(),
+                count,
+                dealt,
+                count * outlet.scry
             );
+        }
+    }
+
+    /// The answer the opponent casts in response to the activation, if any.
+    fn respond(&mut self) -> Option<Answer> {
+        if let Some(answer) = self.held_answer {
+            let spend = match answer {
+                Answer::Removal => true,
+                Answer::Counter => {
+                    let dealt = self.replace_damage(i32::max(self.loyalty(), 0) as u32, true);
+                    self.total_damage().saturating_add(dealt) >= self.target_dmg
+                }
+            };
+
+            if spend {
+                self.held_answer = None;
+                return Some(answer);
+            }
+        }
+
+        let Interaction {
+            counter_chance,
+            removal_chance,
+            ..
+        } = self.board.interaction;
+
+        // Only roll for the answers that are possible, so that seeded games without interaction
+        // roll the same dice as before
+        if removal_chance > 0.0 && self.rng.gen_bool(removal_chance) {
+            Some(Answer::Removal)
+        } else if counter_chance > 0.0 && self.rng.gen_bool(counter_chance) {
+            Some(Answer::Counter)
+        } else {
+            None
+        }
+    }
+
+    /// Mana that can be spent without cutting into what the mana policy keeps for paid activations.
+    pub fn spare_mana(&self) -> u32 {
+        let reserved = match self.board.mana_policy {
+            ManaPolicy::ActivationsFirst => self
+                .paid_activations_left
+                .iter()
+                .map(|paid| paid.cost * paid.uses)
+                .sum(),
+            ManaPolicy::CopiesFirst => 0,
+        };
+
+        self.mana.saturating_sub(reserved)
+    }
+
+    /// Pays for as many copies of the activated ability as the strategy wants.
+    fn copy_ability(&mut self) -> usize {
+        let strategy = Arc::clone(&self.strategy);
+
+        let mut copies = 0;
+        for copy in self.board.copy_effects.clone() {
+            if self.mana >= copy.cost && strategy.copy_ability(self, copy.cost) {
+                self.mana -= copy.cost;
+                copies += 1;
+
+                log::debug!("Paid {} mana to copy the ability.", copy.cost);
+            }
+        }
+
+        self.copies += copies;
+        copies
+    }
+
+    /// Rolls the die with the roll advantage, keeping the roll picked by `choose`.
+    fn roll(&mut self, die: u32, choose: impl FnOnce(&Self, &[u32]) -> usize) -> u32 {
+        let rolls_to_take = 1 + self.roll_advantage;
+        self.rolls += rolls_to_take;
+
+        let die_range = Uniform::new_inclusive::<u32, u32>(1, die);
+        let rolls: Vec<u32> = (&mut self.rng)
+            .sample_iter(die_range)
+            .take(rolls_to_take)
+            .inspect(|roll| log::debug!("Rolled a {roll}."))
+            .collect();
+        let rolls: Vec<u32> = rolls
+            .into_iter()
+            .map(|roll| self.modify_roll(roll, die))
+            .collect();
+
+        let kept = rolls.get(choose(self, &rolls)).copied().unwrap_or(0);
+
+        log::debug!("Kept the roll of {kept}.");
+
+        if self.damage_on_roll > 0 {
+            let dealt = self.replace_damage(self.damage_on_roll, true);
+            for index in 0..self.opponents.len() {
+                self.damage_opponent(index, dealt);
+            }
+            log::debug!("Rolling the dice dealt {dealt} damage to each opponent.");
+        }
+
+        self.trigger("on_roll", (kept as i64,));
+
+        kept
+    }
+
+    /// Result of a rolled die after the roll modifiers of the scripts.
+    fn modify_roll(&self, roll: u32, die: u32) -> u32 {
+        let modified = self.board.scripts.iter().fold(roll, |roll, script| {
+            script
+                .call::<i64>("modify_roll", self.script_game(), (roll as i64,))
+                .map(|(modified, _)| modified.clamp(1, die as i64) as u32)
+                .unwrap_or(roll)
+        });
+
+        if modified != roll {
+            log::debug!("Modified the roll of {roll} to {modified}.");
+        }
+
+        modified
+    }
+
+    /// Calls the hook of every script, taking the actions of the scripts.
+    fn trigger(&mut self, hook: &str, args: impl rhai::FuncArgs + Clone) {
+        for index in 0..self.board.scripts.len() {
+            let script = &self.board.scripts[index];
+            let result = script.call::<rhai::Dynamic>(hook, self.script_game(), args.clone());
+            if let Some((_, actions)) = result {
+                self.take_actions(actions);
+            }
+        }
+    }
+
+    /// What the scripts see of the game.
+    fn script_game(&self) -> ScriptGame {
+        ScriptGame {
+            loyalty: self.loyalty() as i64,
+            activations: self.free_activations_left() as i64,
+            damage: self.damage as i64,
+            squirrels: self.squirrels_on_battlefield() as i64,
+            mana: self.mana as i64,
+            roll_advantage: self.roll_advantage as i64,
+            target_damage: self.target_dmg as i64,
+            actions: Actions::default(),
+        }
+    }
+
+    fn take_actions(&mut self, actions: Actions) {
+        if actions.damage > 0 {
+            let dealt = self.replace_damage(actions.damage, false);
+            let target = Arc::clone(&self.strategy).opponent_target(self, dealt);
+            self.damage_opponent(target, dealt);
+            log::debug!("A script dealt {dealt} damage to Opponent {}.", target + 1);
+        }
+
+        if actions.squirrels > 0 {
+            let created = self.create_tokens(actions.squirrels);
+            self.squirrels = self.squirrels.saturating_add(created);
+            log::debug!("A script created {created} 1/1 green Squirrel creature tokens.");
+        }
+
+        let comet = self.comet_mut();
+        comet.loyalty = comet.loyalty.saturating_add(actions.loyalty);
+        comet.activations_left = comet.activations_left.saturating_add(actions.activations);
+        self.mana = self.mana.saturating_add(actions.mana);
+        let limit = usize::max(self.roll_advantage, MAX_SCRIPT_ROLL_ADVANTAGE);
+        self.roll_advantage = usize::min(self.roll_advantage + actions.roll_advantage as usize, limit);
+    }
+
+    fn resolve(&mut self) {
+        let strategy = Arc::clone(&self.strategy);
+        let kept = self.roll(self.board.ability.die, |game, rolls| {
+            strategy.choose_roll(game, rolls)
+        });
+
+        let effects = self.board.ability.effects(kept).to_vec();
+        let description = self.apply_effects(&effects, true);
+
+        log::debug!(
+            "[Loyalty: {}][Activations: {}] {}",
+            self.loyalty(),
+            self.activations_left(),
+            description
+        );
+    }
 
-        // 6 — [+1], and you may activate Comet, Stellar Pup’s loyalty ability two more times this turn.
-        } else if max_roll == 6 {
-            self.loyalty += 1;
-            self.activations_left += 2;
+    /// Rolls to visit the open Attractions, visiting each of them with the roll lit.
+    fn visit_attractions(&mut self) {
+        if self.open_attractions.is_empty() {
+            return;
+        }
+
+        log::debug!("Roll to visit your Attractions.");
+
+        let strategy = Arc::clone(&self.strategy);
+        let roll = self.roll(VISIT_DIE, |game, rolls| strategy.choose_visit(game, rolls));
+
+        for index in self.open_attractions.clone() {
+            let attraction = &self.board.attractions.attractions[index];
+            if !attraction.is_lit(roll) {
+                continue;
+            }
+
+            let (name, effects) = (attraction.name.clone(), attraction.visit.clone());
+            self.visits[index] += 1;
+
+            // Tokens created at the beginning of combat don't have haste
+            let description = self.apply_effects(&effects, false);
+            log::debug!("Visited {name}. {description}");
+        }
+    }
+
+    /// Opens the top Attraction of the Attraction deck, describing what happened.
+    fn open_attraction(&mut self) -> String {
+        match self.attraction_deck.pop() {
+            Some(index) => {
+                self.open_attractions.push(index);
+                format!("Open {}.", self.board.attractions.attractions[index].name)
+            }
+            None => "No Attractions left to open.".to_owned(),
+        }
+    }
+
+    /// The Attractions open on the battlefield.
+    pub fn open_attractions(&self) -> impl Iterator<Item = &Attraction> {
+        self.open_attractions
+            .iter()
+            .map(|index| &self.board.attractions.attractions[*index])
+    }
+
+    /// Applies the effects in order, describing what happened.
+    fn apply_effects(&mut self, effects: &[Effect], haste: bool) -> String {
+        let mut loyalty_change = 0;
+        let mut descriptions = Vec::new();
+
+        for effect in effects.iter().copied() {
+            match effect {
+                Effect::Loyalty(change) => {
+                    let comet = self.comet_mut();
+                    comet.loyalty = comet.loyalty.saturating_add(change);
+                    loyalty_change += change;
+                }
+                Effect::Squirrels(amount) => {
+                    let created = self.create_tokens(amount);
+                    self.squirrels = self.squirrels.saturating_add(created);
+                    if !haste {
+                        self.summoning_sick = self.summoning_sick.saturating_add(created);
+                    }
+                    descriptions.push(format!("Create {created} 1/1 green Squirrel creature tokens."));
+                }
+                Effect::LoyaltyDamage => {
+                    let loyalty = i32::max(self.loyalty(), 0) as u32;
+                    descriptions.push(self.deal_damage(loyalty));
+                }
+                Effect::Damage(amount) => descriptions.push(self.deal_damage(amount)),
+                Effect::ExtraActivations(amount) => {
+                    let comet = self.comet_mut();
+                    comet.activations_left = comet.activations_left.saturating_add(amount);
+                    descriptions.push(format!("{amount} extra activations."));
+                }
+                Effect::Treasures(amount) => {
+                    let (created, squirrels) = self.create_treasures(amount);
+                    self.mana = self.mana.saturating_add(created);
+                    self.treasures = self.treasures.saturating_add(created);
+                    self.squirrels = self.squirrels.saturating_add(squirrels);
+                    if !haste {
+                        self.summoning_sick = self.summoning_sick.saturating_add(squirrels);
+                    }
+                    descriptions.push(if squirrels > 0 {
+                        format!("Create {created} Treasure tokens and {squirrels} 1/1 green Squirrel creature tokens.")
+                    } else {
+                        format!("Create {created} Treasure tokens.")
+                    });
+                }
+                Effect::OpenAttraction => descriptions.push(self.open_attraction()),
+                Effect::ReturnCard(max_mana_value) => {
+                    self.returns += 1;
+                    descriptions.push(match self.return_card(max_mana_value) {
+                        Some(card) => format!("Return {} from graveyard to hand.", card.name),
+                        None => format!("No cards with mana value {max_mana_value} or less in graveyard."),
+                    });
+                }
+            }
+        }
+
+        let loyalty_cost = if loyalty_change == 0 {
+            String::new()
+        } else {
+            format!("{loyalty_change:+}: ")
+        };
+
+        format!("{}{}", loyalty_cost, descriptions.join(" "))
+    }
+
+    /// Deals damage from the ability to the target chosen by the strategy, describing what happened.
+    fn deal_damage(&mut self, damage: u32) -> String {
+        let dealt = self.replace_damage(damage, self.board.ability.red);
+        let target = Arc::clone(&self.strategy).damage_target(self, dealt);
+        match target {
+            DamageTarget::Opponent(index) => self.damage_opponent(index, dealt),
+            DamageTarget::Creature(index) => self.damage_blocker(index, dealt),
+        }
+
+        format!("{} deals {} damage to {}.", self.board.ability.name, dealt, target)
+    }
+
+    fn return_card(&mut self, max_mana_value: u32) -> Option<Card> {
+        let eligible: Vec<usize> = (0..self.graveyard.len())
+            .filter(|index| self.graveyard[*index].mana_value <= max_mana_value)
+            .collect();
+        let candidates: Vec<Card> = eligible
+            .iter()
+            .map(|index| self.graveyard[*index].clone())
+            .collect();
+
+        let chosen = Arc::clone(&self.strategy).return_card(self, &candidates)?;
+        let index = *eligible.get(chosen)?;
+
+        let card = self.graveyard.remove(index);
+        self.hand.push(card.clone());
+        Some(card)
+    }
+
+    fn cast_from_hand(&mut self) {
+        let strategy = Arc::clone(&self.strategy);
+
+        while let Some(index) = self
+            .hand
+            .iter()
+            .enumerate()
+            .filter(|(_, card)| card.mana_value <= self.mana && strategy.cast_card(self, card))
+            .max_by_key(|(_, card)| card.value())
+            .map(|(index, _)| index)
+        {
+            let card = self.hand.remove(index);
+            self.mana -= card.mana_value;
+            self.casts += 1;
+
+            match card.effect {
+                CardEffect::RollAdvantage => self.roll_advantage += 1,
+                CardEffect::DamageOnRoll(damage) => self.damage_on_roll += damage,
+                CardEffect::None => {}
+            }
 
             log::debug!(
-                "[Loyalty: {}][Activations: {}] +1: Two extra activations.",
-                self.loyalty,
-                self.activations_left
+                "[Loyalty: {}][Activations: {}] Cast {} for {} mana.",
+                self.loyalty(),
+                self.activations_left(),
+                card.name,
+                card.mana_value
             );
         }
     }
+
+    fn damage_opponent(&mut self, index: usize, damage: u32) {
+        if let Some(dealt) = self.opponent_damage.get_mut(index) {
+            *dealt = dealt.saturating_add(damage);
+            self.damage = self.damage.saturating_add(damage);
+        }
+    }
+
+    fn damage_blocker(&mut self, index: usize, damage: u32) {
+        let Some(toughness) = self.blockers.get_mut(index) else {
+            return;
+        };
+
+        *toughness = toughness.saturating_sub(damage);
+        if *toughness == 0 {
+            self.blockers.remove(index);
+            self.blockers_killed += 1;
+            log::debug!("Destroyed a blocker, {} blockers left.", self.blockers.len());
+        }
+    }
+
+    /// Number of Squirrel tokens created after the token replacement effects.
+    pub fn create_tokens(&self, amount: u32) -> u32 {
+        let (tokens, squirrels) = self.replace_tokens(amount, true);
+        self.script_tokens(tokens + squirrels)
+    }
+
+    /// Number of Treasure tokens created after the token replacement effects, and the Squirrel
+    /// tokens Chatterfang creates with them.
+    pub fn create_treasures(&self, amount: u32) -> (u32, u32) {
+        let (tokens, squirrels) = self.replace_tokens(amount, false);
+        (self.script_tokens(tokens), squirrels)
+    }
+
+    fn replace_tokens(&self, amount: u32, creatures: bool) -> (u32, u32) {
+        // You choose the order as the controller of the tokens, and Chatterfang first lets the
+        // doublers double its Squirrels too
+        let mut order = self.board.token_replacements.clone();
+        order.sort_by_key(|replacement| *replacement != TokenReplacement::Chatterfang);
+
+        order
+            .iter()
+            .fold((amount, 0), |(tokens, squirrels), replacement| {
+                replacement.apply(tokens, squirrels, creatures)
+            })
+    }
+
+    fn script_tokens(&self, tokens: u32) -> u32 {
+        self.board.scripts.iter().fold(tokens, |tokens, script| {
+            script
+                .call::<i64>("replace_tokens", self.script_game(), (tokens as i64,))
+                .map(|(replaced, _)| script::replaced(tokens, replaced))
+                .unwrap_or(tokens)
+        })
+    }
+
+    /// Damage dealt by a source after the damage replacement effects.
+    pub fn replace_damage(&self, damage: u32, red: bool) -> u32 {
+        // A source dealing no damage isn't dealing damage at all, so nothing gets replaced
+        if damage == 0 {
+            return 0;
+        }
+
+        // Adding before multiplying deals the most damage and multiplying first the least
+        let mut order = self.board.damage_replacements.clone();
+        match self.board.damage_order {
+            ReplacementOrder::Listed => {}
+            ReplacementOrder::MostDamage => order.sort_by_key(|replacement| !replacement.is_additive()),
+            ReplacementOrder::LeastDamage => order.sort_by_key(|replacement| replacement.is_additive()),
+        }
+
+        let dealt = order
+            .iter()
+            .fold(damage, |damage, replacement| replacement.apply(damage, red));
+
+        self.board.scripts.iter().fold(dealt, |dealt, script| {
+            script
+                .call::<i64>("replace_damage", self.script_game(), (dealt as i64,))
+                .map(|(replaced, _)| script::replaced(dealt, replaced))
+                .unwrap_or(dealt)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::Settings;
+
+    fn settings(board: Board) -> Settings {
+        Settings {
+            squirrels: true,
+            advantage: 0,
+            loyalty: 5,
+            damage: 20,
+            board,
+        }
+    }
+
+    fn game(board: Board) -> Game {
+        settings(board).game().with_seed(1)
+    }
+
+    /// An ability rolling a one-sided die, so that every activation has the same effects.
+    fn fixed(effects: Vec<Effect>) -> Ability {
+        Ability {
+            name: "Fixed".to_owned(),
+            red: false,
+            die: 1,
+            bands: vec![crate::ability::Band { from: 1, to: 1, effects }],
+        }
+    }
+
+    #[test]
+    fn extra_and_paid_activations_add_to_the_first_one() {
+        let board = |mana: u32| Board {
+            ability: fixed(vec![Effect::Damage(1), Effect::Loyalty(-1)]),
+            extra_activations: 1,
+            paid_activations: vec![PaidActivation::CHAIN_VEIL],
+            mana,
+            ..Board::default()
+        };
+
+        let result = game(board(4)).run();
+        assert_eq!(result.rolls, 3);
+        assert_eq!((result.extra_activations, result.paid_activations), (1, 1));
+        assert_eq!((result.damage, result.loyalty), (3, 2));
+
+        // Without the mana The Chain Veil can't be used
+        let result = game(board(3)).run();
+        assert_eq!(result.rolls, 2);
+        assert_eq!((result.extra_activations, result.paid_activations), (1, 0));
+    }
+
+    #[test]
+    fn copies_resolve_separately_while_there_is_mana() {
+        let result = game(Board {
+            ability: fixed(vec![Effect::Damage(1), Effect::Loyalty(-1)]),
+            extra_activations: 1,
+            copy_effects: vec![CopyEffect::RINGS_OF_BRIGHTHEARTH],
+            mana: 4,
+            ..Board::default()
+        })
+        .run();
+
+        assert_eq!((result.copies, result.rolls), (2, 4));
+        assert_eq!((result.damage, result.loyalty), (4, 1));
+    }
+
+    #[test]
+    fn mana_policy_splits_the_mana_between_copies_and_activations() {
+        let result = |mana_policy: ManaPolicy| {
+            game(Board {
+                ability: fixed(vec![Effect::Damage(1), Effect::Loyalty(-1)]),
+                paid_activations: vec![PaidActivation::CHAIN_VEIL],
+                copy_effects: vec![CopyEffect::RINGS_OF_BRIGHTHEARTH],
+                mana_policy,
+                mana: 4,
+                ..Board::default()
+            })
+            .run()
+        };
+
+        let activations_first = result(ManaPolicy::ActivationsFirst);
+        assert_eq!((activations_first.copies, activations_first.paid_activations), (0, 1));
+
+        let copies_first = result(ManaPolicy::CopiesFirst);
+        assert_eq!((copies_first.copies, copies_first.paid_activations), (1, 0));
+    }
+
+    #[test]
+    fn returned_cards_are_cast_for_the_rest_of_the_chain() {
+        let result = |graveyard: Vec<Card>, mana: u32| {
+            game(Board {
+                ability: fixed(vec![Effect::ReturnCard(2), Effect::Loyalty(-1)]),
+                extra_activations: 1,
+                graveyard,
+                mana,
+                ..Board::default()
+            })
+            .run()
+        };
+
+        // Pixie Guide is returned over the card doing nothing, and the second activation rolls twice
+        let other = Card::new("Other card", 2, CardEffect::None);
+        let pixie_guide = result(vec![other.clone(), Card::pixie_guide()], 2);
+        assert_eq!((pixie_guide.returns, pixie_guide.casts), (2, 1));
+        assert_eq!(pixie_guide.rolls, 3);
+
+        let brazen_dwarf = result(vec![Card::brazen_dwarf()], 2);
+        assert_eq!((brazen_dwarf.casts, brazen_dwarf.damage), (1, 1));
+
+        // Without the mana the returned card stays in hand
+        let no_mana = result(vec![Card::pixie_guide()], 1);
+        assert_eq!((no_mana.returns, no_mana.casts, no_mana.rolls), (2, 0, 2));
+    }
+
+    #[test]
+    fn combat_timing_decides_which_squirrels_attack() {
+        let attack = |combat_timing: CombatTiming| {
+            let result = game(Board {
+                ability: fixed(vec![Effect::Squirrels(2), Effect::Loyalty(-5)]),
+                squirrels: 3,
+                squirrel_anthems: 1,
+                blockers: vec![5],
+                combat_timing,
+                ..Board::default()
+            })
+            .run();
+            (result.attackers, result.blocked, result.squirrel_damage)
+        };
+
+        // The hasty squirrels from the chain only attack when Comet is activated first
+        assert_eq!(attack(CombatTiming::BeforeCombat), (5, 1, 8));
+        assert_eq!(attack(CombatTiming::AfterCombat), (3, 1, 4));
+    }
+
+    #[test]
+    fn sacrifice_outlets_turn_the_squirrels_into_damage() {
+        let settings = |squirrels: bool, sacrifice_outlets: Vec<SacrificeOutlet>| {
+            let mut settings = settings(Board {
+                ability: fixed(vec![Effect::Squirrels(2), Effect::Loyalty(-5)]),
+                damage_replacements: vec![DamageReplacement::Torbran],
+                sacrifice_outlets,
+                ..Board::default()
+            });
+            settings.squirrels = squirrels;
+            settings
+        };
+
+        // Without combat the squirrels go to the outlet dealing damage as soon as they are made
+        let outlets = vec![SacrificeOutlet::VISCERA_SEER, SacrificeOutlet::GOBLIN_BOMBARDMENT];
+        let result = settings(false, outlets).game().with_seed(1).run();
+        assert_eq!((result.sacrificed, result.sacrifice_damage, result.scry), (2, 6, 0));
+        assert_eq!(result.damage, 6);
+
+        let result = settings(false, vec![SacrificeOutlet::VISCERA_SEER]).game().with_seed(1).run();
+        assert_eq!((result.sacrificed, result.sacrifice_damage, result.scry), (2, 0, 2));
+
+        // With combat the squirrels attack first and are sacrificed after combat damage
+        let result = settings(true, vec![SacrificeOutlet::GOBLIN_BOMBARDMENT]).game().with_seed(1).run();
+        assert_eq!((result.attackers, result.squirrel_damage), (2, 2));
+        assert_eq!((result.sacrificed, result.sacrifice_damage), (2, 6));
+    }
+
+    #[test]
+    fn parses_the_sacrifice_outlets() {
+        assert_eq!("goblin-bombardment".parse(), Ok(SacrificeOutlet::GOBLIN_BOMBARDMENT));
+        assert_eq!(
+            "scry:2".parse(),
+            Ok(SacrificeOutlet {
+                damage: 0,
+                scry: 2,
+                red: false
+            })
+        );
+        assert!("damage:-1".parse::<SacrificeOutlet>().is_err());
+        assert!("altar".parse::<SacrificeOutlet>().is_err());
+    }
+
+    #[test]
+    fn damage_goes_to_the_opponent_closest_to_being_eliminated() {
+        let result = game(Board {
+            ability: fixed(vec![Effect::Damage(2), Effect::Loyalty(-1)]),
+            extra_activations: 3,
+            opponents: vec![3, 3, 3],
+            ..Board::default()
+        })
+        .run();
+
+        assert!(matches!(result.outcome, Outcome::Lose));
+        assert_eq!((result.damage, result.eliminated), (8, 2));
+        assert_eq!(result.life_left, vec![0, 0, 3]);
+
+        let result = game(Board {
+            ability: fixed(vec![Effect::Loyalty(-5)]),
+            squirrels: 4,
+            opponents: vec![5, 4],
+            ..Board::default()
+        })
+        .run();
+
+        assert_eq!((result.squirrel_damage, result.eliminated), (4, 1));
+        assert_eq!(result.life_left, vec![5, 0]);
+    }
+
+    #[test]
+    fn the_opponent_counters_and_removes_comet() {
+        let result = |interaction: Interaction| {
+            game(Board {
+                ability: fixed(vec![Effect::Damage(1), Effect::Loyalty(-1)]),
+                extra_activations: 1,
+                interaction,
+                ..Board::default()
+            })
+            .run()
+        };
+
+        let countered = result(Interaction {
+            counter_chance: 1.0,
+            ..Interaction::default()
+        });
+        assert_eq!((countered.countered, countered.rolls, countered.damage), (2, 0, 0));
+
+        // The activation on the stack still resolves after the removal
+        for interaction in [
+            Interaction {
+                removal_chance: 1.0,
+                ..Interaction::default()
+            },
+            Interaction {
+                held: Some(Answer::Removal),
+                ..Interaction::default()
+            },
+        ] {
+            let removed = result(interaction);
+            assert!(removed.removed);
+            assert_eq!((removed.rolls, removed.damage, removed.loyalty), (1, 1, 0));
+        }
+    }
+
+    #[test]
+    fn a_held_counter_waits_for_the_lethal_activation() {
+        let result = game(Board {
+            ability: fixed(vec![Effect::LoyaltyDamage, Effect::Loyalty(-1)]),
+            extra_activations: 2,
+            opponents: vec![9],
+            interaction: Interaction {
+                held: Some(Answer::Counter),
+                ..Interaction::default()
+            },
+            ..Board::default()
+        })
+        .run();
+
+        // The first activation deals 5, the second would have been lethal and is countered, and the
+        // third deals the other 4
+        assert_eq!(result.countered, 1);
+        assert_eq!((result.damage, result.loyalty), (9, 3));
+        assert!(matches!(result.outcome, Outcome::Win));
+    }
+
+    #[test]
+    fn every_comet_has_its_own_loyalty_and_activations() {
+        let result = |mana: u32| {
+            game(Board {
+                ability: fixed(vec![Effect::Damage(1), Effect::Loyalty(-1)]),
+                extra_comets: vec![SPARK_DOUBLE_LOYALTY],
+                paid_activations: vec![PaidActivation::CHAIN_VEIL],
+                mana,
+                ..Board::default()
+            })
+            .run()
+        };
+
+        let without_mana = result(0);
+        assert_eq!((without_mana.rolls, without_mana.damage), (2, 2));
+        assert_eq!(without_mana.loyalties, vec![4, 5]);
+
+        // The Chain Veil lets both of the Comets activate once more
+        let with_mana = result(4);
+        assert_eq!((with_mana.rolls, with_mana.paid_activations), (4, 1));
+        assert_eq!(with_mana.loyalties, vec![3, 4]);
+    }
+
+    #[test]
+    fn treasure_presets_add_mana_and_chatterfang_squirrels() {
+        for seed in 0..10 {
+            let result = game(Board {
+                ability: Ability::preset("bucknards-everfull-purse").unwrap(),
+                token_replacements: vec![TokenReplacement::Chatterfang],
+                ..Board::default()
+            })
+            .with_seed(seed)
+            .run();
+
+            assert_eq!(result.rolls, 1);
+            assert!((1..=4).contains(&result.treasures));
+            assert_eq!(result.squirrels, result.treasures);
+        }
+    }
+
+    #[test]
+    fn open_attractions_are_visited_at_the_beginning_of_combat() {
+        let result = |effects: Vec<Effect>, open: usize, visit: Vec<Effect>, timing: CombatTiming| {
+            let attractions = vec![Attraction {
+                name: "Always lit".to_owned(),
+                lit: (1..=VISIT_DIE).collect(),
+                visit,
+            }];
+
+            game(Board {
+                ability: fixed(effects),
+                attractions: AttractionDeck { attractions, open },
+                combat_timing: timing,
+                ..Board::default()
+            })
+            .run()
+        };
+
+        let gone = vec![Effect::Loyalty(-5)];
+        let visited = result(gone.clone(), 1, vec![Effect::Damage(2)], CombatTiming::BeforeCombat);
+        assert_eq!(visited.visits, vec![1]);
+        assert_eq!((visited.damage, visited.rolls), (2, 2));
+
+        // The Attraction opened by the chain is only visited when the chain is before combat
+        let opened = vec![Effect::OpenAttraction, Effect::Loyalty(-5)];
+        let before = result(opened.clone(), 0, vec![Effect::Damage(2)], CombatTiming::BeforeCombat);
+        assert_eq!(before.visits, vec![1]);
+        let after = result(opened, 0, vec![Effect::Damage(2)], CombatTiming::AfterCombat);
+        assert_eq!(after.visits, vec![0]);
+
+        // The squirrels created by the visit don't have haste
+        let squirrels = result(gone, 1, vec![Effect::Squirrels(2)], CombatTiming::BeforeCombat);
+        assert_eq!((squirrels.squirrels, squirrels.attackers), (2, 0));
+    }
+
+    #[test]
+    fn custom_strategies_make_the_decisions() {
+        #[derive(Debug)]
+        struct Passive;
+
+        impl Strategy for Passive {
+            fn keep_activating(&self, _game: &Game) -> bool {
+                false
+            }
+        }
+
+        #[derive(Debug)]
+        struct KeepLowest;
+
+        impl Strategy for KeepLowest {
+            fn choose_roll(&self, _game: &Game, rolls: &[u32]) -> usize {
+                rolls
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, roll)| **roll)
+                    .map(|(index, _)| index)
+                    .unwrap_or(0)
+            }
+        }
+
+        let result = game(Board::default()).with_strategy(Arc::new(Passive)).run();
+        assert_eq!((result.rolls, result.loyalty), (0, 5));
+
+        // Only a 6 deals no damage, so keeping the lowest of many dice always deals 1
+        let mut settings = settings(Board {
+            ability: Ability {
+                name: "Low roller".to_owned(),
+                red: false,
+                die: 6,
+                bands: vec![crate::ability::Band {
+                    from: 1,
+                    to: 5,
+                    effects: vec![Effect::Damage(1)],
+                }],
+            },
+            ..Board::default()
+        });
+        settings.advantage = 20;
+
+        for seed in 0..10 {
+            let result = settings
+                .game()
+                .with_strategy(Arc::new(KeepLowest))
+                .with_seed(seed)
+                .run();
+            assert_eq!((result.rolls, result.damage), (21, 1));
+        }
+    }
+
+    #[test]
+    fn token_doublers_and_chatterfang_multiply_the_squirrels() {
+        use TokenReplacement::*;
+
+        let created = |token_replacements: Vec<TokenReplacement>| {
+            game(Board {
+                token_replacements,
+                ..Board::default()
+            })
+            .create_tokens(2)
+        };
+
+        assert_eq!(created(vec![]), 2);
+        assert_eq!(created(vec![Doubler]), 4);
+        assert_eq!(created(vec![Doubler, Doubler]), 8);
+        assert_eq!(created(vec![CreatureDoubler]), 4);
+        assert_eq!(created(vec![Chatterfang]), 4);
+        assert_eq!(created(vec![Doubler, Chatterfang]), 8);
+    }
+
+    #[test]
+    fn token_replacements_create_the_most_tokens_in_any_listed_order() {
+        use TokenReplacement::*;
+
+        for token_replacements in [vec![CreatureDoubler, Chatterfang], vec![Chatterfang, CreatureDoubler]] {
+            let game = game(Board {
+                token_replacements,
+                ..Board::default()
+            });
+
+            // Chatterfang's Squirrels are creatures even when the tokens are Treasures
+            assert_eq!(game.create_treasures(1), (1, 2));
+            assert_eq!(game.create_tokens(1), 4);
+        }
+    }
+
+    #[test]
+    fn damage_order_chooses_when_torbran_adds() {
+        let dealt = |damage_order: ReplacementOrder, damage: u32, red: bool| {
+            game(Board {
+                damage_replacements: vec![DamageReplacement::Torbran, DamageReplacement::Doubler],
+                damage_order,
+                ..Board::default()
+            })
+            .replace_damage(damage, red)
+        };
+
+        assert_eq!(dealt(ReplacementOrder::Listed, 3, true), 10);
+        assert_eq!(dealt(ReplacementOrder::MostDamage, 3, true), 10);
+        assert_eq!(dealt(ReplacementOrder::LeastDamage, 3, true), 8);
+
+        // Torbran only adds to red sources, and a source dealing no damage isn't replaced
+        assert_eq!(dealt(ReplacementOrder::MostDamage, 3, false), 6);
+        assert_eq!(dealt(ReplacementOrder::MostDamage, 0, true), 0);
+    }
+
+    #[test]
+    fn damage_order_sorts_every_multiplier_after_torbran() {
+        let dealt = |damage_order: ReplacementOrder| {
+            game(Board {
+                damage_replacements: vec![
+                    DamageReplacement::FieryEmancipation,
+                    DamageReplacement::Torbran,
+                    DamageReplacement::Doubler,
+                ],
+                damage_order,
+                ..Board::default()
+            })
+            .replace_damage(1, true)
+        };
+
+        assert_eq!(dealt(ReplacementOrder::Listed), 10);
+        assert_eq!(dealt(ReplacementOrder::MostDamage), 18);
+        assert_eq!(dealt(ReplacementOrder::LeastDamage), 8);
+    }
+
+    #[test]
+    fn hostile_scripts_cant_overflow_the_game() {
+        let script = Script::new(
+            "hostile",
+            "fn on_activate() { this.deal_damage(4294967295); this.create_squirrels(4294967295); this.add_activations(4294967295); }
+             fn replace_damage(damage) { 4294967295 }
+             fn replace_tokens(tokens) { 4294967295 }",
+        )
+        .unwrap();
+        let settings = settings(Board {
+            token_replacements: vec![TokenReplacement::Doubler, TokenReplacement::Chatterfang],
+            damage_replacements: vec![DamageReplacement::Torbran, DamageReplacement::FieryEmancipation],
+            sacrifice_outlets: vec![SacrificeOutlet::GOBLIN_BOMBARDMENT],
+            scripts: vec![script],
+            ..Board::default()
+        });
+
+        let game = settings.game();
+        assert_eq!(game.replace_damage(1, true), 1_000);
+        assert_eq!(game.create_tokens(1), 1_000);
+
+        for seed in 0..10 {
+            let result = settings.game().with_seed(seed).run();
+            assert!(matches!(result.outcome, Outcome::Win));
+            assert!(result.total_damage >= result.damage);
+        }
+    }
+
+    #[test]
+    fn endless_tokens_saturate_instead_of_overflowing() {
+        let script = Script::new(
+            "hostile",
+            "fn replace_damage(damage) { 4294967295 }
+             fn replace_tokens(tokens) { 4294967295 }",
+        )
+        .unwrap();
+        let mut settings = settings(Board {
+            ability: Ability {
+                name: "Squirrel loop".to_owned(),
+                red: false,
+                die: 1,
+                bands: vec![crate::ability::Band {
+                    from: 1,
+                    to: 1,
+                    effects: vec![Effect::Squirrels(1), Effect::ExtraActivations(1)],
+                }],
+            },
+            token_replacements: vec![TokenReplacement::Doubler, TokenReplacement::Chatterfang],
+            scripts: vec![script],
+            ..Board::default()
+        });
+        settings.squirrels = false;
+
+        let result = settings.game().with_seed(1).run();
+        assert_eq!(result.squirrels, 10_000 * 1_000);
+        assert_eq!(result.squirrel_damage, u32::MAX);
+    }
 }
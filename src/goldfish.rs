@@ -0,0 +1,332 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::decklist::{Decklist, Entry, KnownCard, Modifier};
+use crate::game::{Outcome, SPARK_DOUBLE_LOYALTY};
+use crate::simulator::Settings;
+use crate::strategy::Strategy;
+use crate::turns;
+
+const COMET: &str = "Comet, Stellar Pup";
+const HAND_SIZE: usize = 7;
+/// Mulligans taken at most, keeping whatever the last hand is.
+const MAX_MULLIGANS: usize = 3;
+
+/// How the deck is played without an opponent interacting with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goldfish {
+    /// Turns to play before giving up
+    pub turns: usize,
+    /// Is the first card drawn on the first turn
+    pub on_the_draw: bool,
+}
+
+/// Result of goldfishing the deck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldfishResult {
+    pub mulligans: usize,
+    /// The turn Comet was first cast on, starting from 1
+    pub cast_turn: Option<usize>,
+    /// The turn every opponent was eliminated on, starting from 1
+    pub won_turn: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeckCard {
+    Comet(u32),
+    Land,
+    Modifier(&'static KnownCard),
+    /// A card the simulator doesn't know, never cast
+    Blank,
+}
+
+impl DeckCard {
+    fn new(entry: &Entry) -> Self {
+        match KnownCard::find(&entry.name) {
+            _ if entry.land => DeckCard::Land,
+            Some(card) if card.land => DeckCard::Land,
+            Some(card) if card.name == COMET => DeckCard::Comet(card.mana_value),
+            Some(card) if card.modifier.is_some() => DeckCard::Modifier(card),
+            _ => DeckCard::Blank,
+        }
+    }
+
+    fn mana_value(&self) -> u32 {
+        match self {
+            DeckCard::Comet(mana_value) => *mana_value,
+            DeckCard::Modifier(card) => card.mana_value,
+            DeckCard::Land | DeckCard::Blank => 0,
+        }
+    }
+}
+
+/// The cards of a decklist as the goldfish sees them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    library: Vec<DeckCard>,
+    command_zone: Vec<DeckCard>,
+}
+
+impl Deck {
+    pub fn new(decklist: &Decklist) -> Result<Self, String> {
+        let cards = |entries: &[Entry]| -> Vec<DeckCard> {
+            entries
+                .iter()
+                .flat_map(|entry| vec![DeckCard::new(entry); entry.count as usize])
+                .collect()
+        };
+
+        let deck = Deck {
+            library: cards(&decklist.main),
+            command_zone: cards(&decklist.commander),
+        };
+
+        let has_comet = |cards: &[DeckCard]| cards.iter().any(|card| matches!(card, DeckCard::Comet(_)));
+        if !has_comet(&deck.library) && !has_comet(&deck.command_zone) {
+            return Err(format!("invalid deck: no {COMET} in the decklist"));
+        }
+
+        if deck.library.len() < HAND_SIZE {
+            return Err(format!("invalid deck: fewer than {HAND_SIZE} cards in the library"));
+        }
+
+        Ok(deck)
+    }
+
+    /// Number of lands in the library.
+    pub fn lands(&self) -> usize {
+        self.library.iter().filter(|card| **card == DeckCard::Land).count()
+    }
+
+    /// Number of cards in the library the simulator doesn't know.
+    pub fn blanks(&self) -> usize {
+        self.library.iter().filter(|card| **card == DeckCard::Blank).count()
+    }
+
+    /// Are there so few lands that some of them are likely missing from the registry, with
+    /// less than a quarter of the library being lands.
+    pub fn has_few_lands(&self) -> bool {
+        self.lands() * 4 < self.library.len()
+    }
+}
+
+/// Shuffles the deck, mulligans and plays it for up to `goldfish.turns` turns.
+///
+/// Hands with two to five lands are kept, and the London mulligan puts the extra lands and the
+/// unknown cards on the bottom first. Every turn a land is played, Comet is cast as soon as there
+/// is mana for it and the known cards are cast with the mana left over, most expensive first. Once
+/// Comet is on the battlefield it is activated every turn like with `turns::play`, carrying its
+/// loyalty, the squirrels and the opponents' life totals over with `turns::carry_over`, and with
+/// the mana left after casting the cards available for the activations.
+pub fn play(
+    deck: &Deck,
+    settings: &Settings,
+    strategy: Arc<dyn Strategy>,
+    goldfish: Goldfish,
+    seed: u64,
+) -> GoldfishResult {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut hand, mut library, mulligans) = opening_hand(deck, &mut rng);
+    hand.extend(deck.command_zone.iter().copied());
+
+    let mut settings = settings.clone();
+    let mut lands = 0;
+    let mut battlefield: Vec<Modifier> = Vec::new();
+    let mut comets: Vec<i32> = Vec::new();
+    let mut cast_turn = None;
+
+    for turn in 1..=goldfish.turns {
+        if turn > 1 || goldfish.on_the_draw {
+            hand.extend(library.pop());
+        }
+
+        if let Some(index) = hand.iter().position(|card| *card == DeckCard::Land) {
+            hand.remove(index);
+            lands += 1;
+        }
+
+        let mut mana = lands;
+
+        if comets.is_empty() {
+            let comet = hand
+                .iter()
+                .position(|card| matches!(card, DeckCard::Comet(mana_value) if *mana_value <= mana));
+
+            if let Some(index) = comet {
+                mana -= hand.remove(index).mana_value();
+                comets.push(settings.loyalty);
+                cast_turn.get_or_insert(turn);
+                log::debug!("[Turn: {turn}] Cast Comet.");
+            }
+        }
+
+        while let Some(index) = castable(&hand, mana, !comets.is_empty()) {
+            let card = hand.remove(index);
+            mana -= card.mana_value();
+
+            if let DeckCard::Modifier(card) = card {
+                log::debug!("[Turn: {turn}] Cast {}.", card.name);
+                match card.modifier {
+                    Some(Modifier::SparkDouble) => comets.push(SPARK_DOUBLE_LOYALTY),
+                    Some(modifier) => battlefield.push(modifier),
+                    None => {}
+                }
+            }
+        }
+
+        if comets.is_empty() {
+            continue;
+        }
+
+        let mut turn_settings = settings.clone();
+        turn_settings.loyalty = comets[0];
+        turn_settings.board.extra_comets = comets[1..].to_vec();
+        turn_settings.board.mana += mana;
+        for modifier in battlefield.iter() {
+            modifier.apply(&mut turn_settings);
+        }
+
+        let result = turn_settings
+            .game()
+            .with_strategy(Arc::clone(&strategy))
+            .with_seed(rng.gen())
+            .run();
+
+        if matches!(result.outcome, Outcome::Win) {
+            return GoldfishResult {
+                mulligans,
+                cast_turn,
+                won_turn: Some(turn),
+            };
+        }
+
+        comets = turns::carry_over(&mut settings, &result);
+    }
+
+    GoldfishResult {
+        mulligans,
+        cast_turn,
+        won_turn: None,
+    }
+}
+
+/// Draws hands until one with two to five lands, putting a card on the bottom for each mulligan.
+fn opening_hand(deck: &Deck, rng: &mut StdRng) -> (Vec<DeckCard>, Vec<DeckCard>, usize) {
+    let mut mulligans = 0;
+
+    loop {
+        let mut library = deck.library.clone();
+        library.shuffle(rng);
+        let mut hand = library.split_off(library.len() - HAND_SIZE);
+
+        let lands = hand.iter().filter(|card| **card == DeckCard::Land).count();
+        if (2..=5).contains(&lands) || mulligans == MAX_MULLIGANS {
+            for _ in 0..mulligans {
+                let index = bottom(&hand);
+                library.insert(0, hand.remove(index));
+            }
+
+            return (hand, library, mulligans);
+        }
+
+        mulligans += 1;
+    }
+}
+
+/// The card to put on the bottom: a land over three, an unknown card, the most expensive known
+/// card or a land, in that order.
+fn bottom(hand: &[DeckCard]) -> usize {
+    let lands = hand.iter().filter(|card| **card == DeckCard::Land).count();
+    let position = |wanted: fn(&DeckCard) -> bool| hand.iter().position(wanted);
+
+    (if lands > 3 { position(|card| *card == DeckCard::Land) } else { None })
+        .or_else(|| position(|card| *card == DeckCard::Blank))
+        .or_else(|| {
+            hand.iter()
+                .enumerate()
+                .filter(|(_, card)| matches!(card, DeckCard::Modifier(_)))
+                .max_by_key(|(_, card)| card.mana_value())
+                .map(|(index, _)| index)
+        })
+        .or_else(|| position(|card| *card == DeckCard::Land))
+        .unwrap_or(0)
+}
+
+/// The most expensive known card in hand there is mana for. Spark Double waits for Comet.
+fn castable(hand: &[DeckCard], mana: u32, comet: bool) -> Option<usize> {
+    hand.iter()
+        .enumerate()
+        .filter(|(_, card)| match card {
+            DeckCard::Modifier(card) => {
+                card.mana_value <= mana && (comet || card.modifier != Some(Modifier::SparkDouble))
+            }
+            _ => false,
+        })
+        .max_by_key(|(_, card)| card.mana_value())
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Board;
+    use crate::strategy::DefaultStrategy;
+
+    fn deck(decklist: &str) -> Result<Deck, String> {
+        Deck::new(&decklist.parse().unwrap())
+    }
+
+    fn settings() -> Settings {
+        Settings {
+            squirrels: true,
+            advantage: 0,
+            loyalty: 5,
+            damage: 20,
+            board: Board::default(),
+        }
+    }
+
+    #[test]
+    fn rejects_decks_that_cant_be_played() {
+        assert!(deck("60 Mountain").is_err());
+        assert!(deck("Commander\n1 Comet, Stellar Pup\n\nDeck\n6 Mountain").is_err());
+    }
+
+    #[test]
+    fn counts_the_lands_and_unknown_cards() {
+        let deck = deck("4 Comet, Stellar Pup\n30 Mountain\n26 Unknown Card").unwrap();
+        assert_eq!((deck.lands(), deck.blanks()), (30, 26));
+        assert!(!deck.has_few_lands());
+    }
+
+    #[test]
+    fn casts_the_commander_once_there_is_mana_for_it() {
+        let deck = deck("Commander\n1 Comet, Stellar Pup\n\nDeck\n99 Mountain").unwrap();
+
+        for on_the_draw in [false, true] {
+            let goldfish = Goldfish {
+                turns: 10,
+                on_the_draw,
+            };
+            let result = play(&deck, &settings(), Arc::new(DefaultStrategy), goldfish, 1);
+
+            // Every hand has seven lands, so the last mulligan is kept
+            assert_eq!(result.mulligans, MAX_MULLIGANS);
+            assert_eq!(result.cast_turn, Some(4));
+        }
+    }
+
+    #[test]
+    fn spark_double_waits_for_comet() {
+        let spark_double = DeckCard::Modifier(KnownCard::find("Spark Double").unwrap());
+        let pixie_guide = DeckCard::Modifier(KnownCard::find("Pixie Guide").unwrap());
+        let hand = [spark_double, pixie_guide];
+
+        assert_eq!(castable(&hand, 4, false), Some(1));
+        assert_eq!(castable(&hand, 4, true), Some(0));
+        assert_eq!(castable(&hand, 1, true), None);
+    }
+}
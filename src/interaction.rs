@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// An instant speed answer the opponent can cast in response to an activation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Answer {
+    /// Counters the activated ability, like Stifle. Copies of it still resolve.
+    Counter,
+    /// Removes Comet, like Bitter Triumph. The abilities already on the stack still resolve, but
+    /// Comet can't be activated again.
+    Removal,
+}
+
+impl FromStr for Answer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "counter" => Ok(Answer::Counter),
+            "removal" => Ok(Answer::Removal),
+            _ => Err(format!("unknown answer \"{s}\", expected counter or removal")),
+        }
+    }
+}
+
+/// How the opponent interacts with the activation chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Chance of each activation being countered
+    pub counter_chance: f64,
+    /// Chance of Comet being removed in response to each activation
+    pub removal_chance: f64,
+    /// A single answer the opponent holds up and spends at the most damaging moment. Removal is
+    /// cast in response to the first activation, and a counter is saved for the first activation
+    /// that would be lethal with a 4 or 5.
+    pub held: Option<Answer>,
+}
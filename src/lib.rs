@@ -1,2 +1,17 @@
 pub mod simulator;
 pub mod game;
+pub mod card;
+pub mod strategy;
+pub mod solver;
+pub mod tournament;
+pub mod combat;
+pub mod interaction;
+pub mod turns;
+pub mod ability;
+pub mod attraction;
+pub mod script;
+pub mod decklist;
+pub mod goldfish;
+pub mod optimiser;
+pub mod scenario;
+pub mod sweep;
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::decklist::KnownCard;
+use crate::game::Outcome;
+use crate::simulator::Settings;
+use crate::solver::{Policy, Solution};
+use crate::strategy;
+
+/// Packages evaluated at most, to keep a search from running for days.
+const MAX_PACKAGES: usize = 100_000;
+
+/// What the optimiser maximises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Probability of dealing the target damage
+    Win,
+    /// Expected total damage
+    Damage,
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "win" => Ok(Objective::Win),
+            "damage" => Ok(Objective::Damage),
+            _ => Err(format!("unknown objective \"{s}\", expected win or damage")),
+        }
+    }
+}
+
+/// How the packages are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The win probability with optimal play from the exact solver
+    Solver,
+    /// Simulated games on identical dice for every package
+    Simulation,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Solver => write!(f, "exact solver"),
+            Method::Simulation => write!(f, "simulation"),
+        }
+    }
+}
+
+/// A set of support cards and its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub cards: Vec<&'static KnownCard>,
+    pub value: f64,
+}
+
+/// How much a card of the best package adds to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    pub card: &'static KnownCard,
+    /// Value of the best package without the card
+    pub without: f64,
+    pub contribution: f64,
+}
+
+/// The best package with a card not in it, swapped in for one of its cards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternative {
+    pub card: &'static KnownCard,
+    pub replaces: &'static KnownCard,
+    pub value: f64,
+}
+
+/// Results of the search, the packages ordered from the best to the worst.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimisation {
+    pub method: Method,
+    pub evaluated: usize,
+    pub packages: Vec<Package>,
+    pub contributions: Vec<Contribution>,
+    pub alternatives: Vec<Alternative>,
+}
+
+/// Searches the support cards maximising the objective for the starting state of the settings.
+///
+/// The candidates with the same modifier, like Anointed Procession and Doubling Season, are
+/// interchangeable and evaluated once, so the packages list only the first of them. With the win
+/// objective the exact solver is used when it models every candidate, and otherwise every package
+/// plays the same simulated games with the same seeds so that the differences between them are
+/// down to the cards.
+#[derive(Debug, Clone)]
+pub struct Optimiser {
+    settings: Settings,
+    /// Interchangeable candidates grouped by their modifier
+    groups: Vec<Vec<&'static KnownCard>>,
+    objective: Objective,
+    method: Method,
+    strategy: String,
+    games: usize,
+    seed: u64,
+}
+
+impl Optimiser {
+    pub fn new(
+        settings: &Settings,
+        candidates: &[&'static KnownCard],
+        objective: Objective,
+        strategy: &str,
+        games: usize,
+        seed: u64,
+    ) -> Result<Self, String> {
+        let mut groups: Vec<Vec<&'static KnownCard>> = Vec::new();
+        for card in candidates.iter().filter(|card| card.modifier.is_some()) {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].modifier == card.modifier)
+            {
+                Some(group) if !group.contains(card) => group.push(card),
+                Some(_) => {}
+                None => groups.push(vec![card]),
+            }
+        }
+
+        if groups.is_empty() {
+            return Err("no support cards to choose from".to_owned());
+        }
+
+        let mut optimiser = Self {
+            settings: settings.clone(),
+            groups,
+            objective,
+            method: Method::Simulation,
+            strategy: strategy.to_owned(),
+            games,
+            seed,
+        };
+
+        let everything: Vec<usize> = (0..optimiser.groups.len()).collect();
+        if objective == Objective::Win
+            && Solution::is_exact(&optimiser.settings(&everything).game())
+        {
+            optimiser.method = Method::Solver;
+        } else {
+            strategy::built_in(strategy, &optimiser.settings.game())?;
+        }
+
+        Ok(optimiser)
+    }
+
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Finds the best packages of `slots` cards, evaluating batches of packages with `evaluate`
+    /// so that the caller can spread them over threads.
+    pub fn optimise<F>(&self, slots: usize, evaluate: F) -> Result<Optimisation, String>
+    where
+        F: Fn(&[Vec<usize>]) -> Result<Vec<f64>, String>,
+    {
+        let available: usize = self.groups.iter().map(Vec::len).sum();
+        if slots == 0 || slots > available {
+            return Err(format!(
+                "can't choose {slots} out of {available} support cards"
+            ));
+        }
+
+        let mut packages = Vec::new();
+        self.packages(slots, 0, &mut Vec::new(), &mut packages);
+        if packages.len() > MAX_PACKAGES {
+            return Err(format!(
+                "too many packages to evaluate, choose fewer slots or candidates to get under {MAX_PACKAGES}"
+            ));
+        }
+
+        let values: HashMap<Vec<usize>, f64> =
+            packages.iter().cloned().zip(evaluate(&packages)?).collect();
+
+        let mut ranked: Vec<(&Vec<usize>, f64)> = values
+            .iter()
+            .map(|(package, value)| (package, *value))
+            .collect();
+        ranked.sort_by(|(a, a_value), (b, b_value)| {
+            b_value
+                .total_cmp(a_value)
+                .then_with(|| self.mana_value(a).cmp(&self.mana_value(b)))
+                .then_with(|| a.cmp(b))
+        });
+
+        let (best, best_value) = (ranked[0].0.clone(), ranked[0].1);
+
+        // The best package with each of its cards removed, skipping the interchangeable duplicates
+        let mut smaller: Vec<Vec<usize>> = Vec::new();
+        for index in 0..best.len() {
+            let mut package = best.clone();
+            package.remove(index);
+            if !smaller.contains(&package) {
+                smaller.push(package);
+            }
+        }
+        let smaller_values: HashMap<Vec<usize>, f64> =
+            smaller.iter().cloned().zip(evaluate(&smaller)?).collect();
+
+        let cards = self.cards(&best);
+        let contributions = best
+            .iter()
+            .enumerate()
+            .map(|(index, _)| {
+                let mut package = best.clone();
+                package.remove(index);
+                let without = smaller_values[&package];
+                Contribution {
+                    card: cards[index],
+                    without,
+                    contribution: best_value - without,
+                }
+            })
+            .collect();
+
+        let mut alternatives: Vec<Alternative> = Vec::new();
+        for group in 0..self.groups.len() {
+            let used = best.iter().filter(|used| **used == group).count();
+            if used == self.groups[group].len() {
+                continue;
+            }
+
+            let swaps = best
+                .iter()
+                .enumerate()
+                .filter(|(_, replaced)| **replaced != group);
+            let alternative = swaps
+                .map(|(index, replaced)| {
+                    let mut package = best.clone();
+                    package[index] = group;
+                    package.sort_unstable();
+                    (*replaced, values[&package])
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            if let Some((replaced, value)) = alternative {
+                alternatives.push(Alternative {
+                    card: self.groups[group][used],
+                    replaces: self.groups[replaced][0],
+                    value,
+                });
+            }
+        }
+        alternatives.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+        Ok(Optimisation {
+            method: self.method,
+            evaluated: values.len() + smaller_values.len(),
+            packages: ranked
+                .into_iter()
+                .map(|(package, value)| Package {
+                    cards: self.cards(package),
+                    value,
+                })
+                .collect(),
+            contributions,
+            alternatives,
+        })
+    }
+
+    /// Value of the package of candidate groups.
+    pub fn evaluate(&self, package: &[usize]) -> Result<f64, String> {
+        let settings = self.settings(package);
+        let game = settings.game();
+
+        if self.method == Method::Solver {
+            let solution = Solution::new(&game, Policy::Optimal);
+            return Ok(solution.value(
+                game.loyalty(),
+                game.free_activations_left(),
+                game.total_damage(),
+            ));
+        }
+
+        let strategy = strategy::built_in(&self.strategy, &game)?;
+        let mut total = 0.0;
+
+        for index in 0..self.games {
+            let result = settings
+                .game()
+                .with_strategy(strategy.clone())
+                .with_seed(self.seed.wrapping_add(index as u64))
+                .run();
+
+            total += match self.objective {
+                Objective::Win => match result.outcome {
+                    Outcome::Win => 1.0,
+                    Outcome::Lose => 0.0,
+                },
+                Objective::Damage => result.total_damage as f64,
+            };
+        }
+
+        Ok(total / usize::max(self.games, 1) as f64)
+    }
+
+    /// The settings with the package on the battlefield.
+    fn settings(&self, package: &[usize]) -> Settings {
+        let mut settings = self.settings.clone();
+        for card in self.cards(package) {
+            if let Some(modifier) = card.modifier {
+                modifier.apply(&mut settings);
+            }
+        }
+
+        settings
+    }
+
+    /// The cards of a package, taking the next interchangeable card for each repeated group.
+    fn cards(&self, package: &[usize]) -> Vec<&'static KnownCard> {
+        package
+            .iter()
+            .enumerate()
+            .map(|(index, group)| {
+                let repeats = package[..index]
+                    .iter()
+                    .filter(|other| *other == group)
+                    .count();
+                self.groups[*group][repeats]
+            })
+            .collect()
+    }
+
+    fn mana_value(&self, package: &[usize]) -> u32 {
+        self.cards(package).iter().map(|card| card.mana_value).sum()
+    }
+
+    /// Every sorted package of `slots` groups, using each group at most as many times as it has
+    /// cards.
+    fn packages(
+        &self,
+        slots: usize,
+        first: usize,
+        package: &mut Vec<usize>,
+        packages: &mut Vec<Vec<usize>>,
+    ) {
+        if packages.len() > MAX_PACKAGES {
+            return;
+        }
+
+        if package.len() == slots {
+            packages.push(package.clone());
+            return;
+        }
+
+        for group in first..self.groups.len() {
+            let used = package.iter().filter(|used| **used == group).count();
+            if used < self.groups[group].len() {
+                package.push(group);
+                self.packages(slots, group, package, packages);
+                package.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Board;
+
+    fn settings() -> Settings {
+        Settings {
+            squirrels: true,
+            advantage: 0,
+            loyalty: 5,
+            damage: 20,
+            board: Board::default(),
+        }
+    }
+
+    fn candidates(names: &[&str]) -> Vec<&'static KnownCard> {
+        names.iter().map(|name| KnownCard::find(name).unwrap()).collect()
+    }
+
+    fn optimiser(names: &[&str], objective: Objective, strategy: &str) -> Result<Optimiser, String> {
+        Optimiser::new(&settings(), &candidates(names), objective, strategy, 10, 1)
+    }
+
+    #[test]
+    fn uses_the_solver_when_it_models_every_candidate() {
+        let exact = optimiser(&["Pixie Guide", "Torbran, Thane of Red Fell"], Objective::Win, "default");
+        assert_eq!(exact.unwrap().method(), Method::Solver);
+
+        let simulated = optimiser(&["Pixie Guide", "Goblin Bombardment"], Objective::Win, "default");
+        assert_eq!(simulated.unwrap().method(), Method::Simulation);
+
+        let damage = optimiser(&["Pixie Guide"], Objective::Damage, "default");
+        assert_eq!(damage.unwrap().method(), Method::Simulation);
+    }
+
+    #[test]
+    fn rejects_what_cant_be_searched() {
+        assert!(optimiser(&["Mountain"], Objective::Win, "default").is_err());
+        assert!(optimiser(&["Pixie Guide"], Objective::Damage, "bogus").is_err());
+
+        // The optimal strategy is only solved for Comet
+        let mut delina = settings();
+        delina.board.ability = crate::ability::Ability::preset("delina").unwrap();
+        let candidates = candidates(&["Pixie Guide"]);
+        assert!(Optimiser::new(&delina, &candidates, Objective::Damage, "optimal", 10, 1).is_err());
+
+        let optimiser = optimiser(&["Pixie Guide"], Objective::Damage, "default").unwrap();
+        assert!(optimiser.optimise(2, |_| unreachable!()).is_err());
+        assert!(optimiser.optimise(0, |_| unreachable!()).is_err());
+    }
+
+    #[test]
+    fn ranks_the_packages_and_their_cards() {
+        let names = [
+            "Pixie Guide",
+            "Anointed Procession",
+            "Doubling Season",
+            "Torbran, Thane of Red Fell",
+        ];
+        let optimiser = optimiser(&names, Objective::Damage, "default").unwrap();
+
+        // Pixie Guide is worth 1, either token doubler 2 and Torbran 4
+        let weights = [1.0, 2.0, 4.0];
+        let evaluate = |packages: &[Vec<usize>]| {
+            Ok(packages
+                .iter()
+                .map(|package| package.iter().map(|group| weights[*group]).sum())
+                .collect())
+        };
+
+        let optimisation = optimiser.optimise(2, evaluate).unwrap();
+        let name = |card: &KnownCard| card.name.clone();
+
+        let best = &optimisation.packages[0];
+        assert_eq!(best.cards.iter().map(|card| name(card)).collect::<Vec<_>>(), [names[1], names[3]]);
+        assert_eq!(best.value, 6.0);
+        assert_eq!(optimisation.packages.len(), 4);
+        assert_eq!(optimisation.evaluated, 6);
+
+        let contributions: Vec<(String, f64)> = optimisation
+            .contributions
+            .iter()
+            .map(|contribution| (name(contribution.card), contribution.contribution))
+            .collect();
+        assert_eq!(contributions, [(names[1].to_owned(), 2.0), (names[3].to_owned(), 4.0)]);
+
+        let alternatives: Vec<(String, String, f64)> = optimisation
+            .alternatives
+            .iter()
+            .map(|alternative| (name(alternative.card), name(alternative.replaces), alternative.value))
+            .collect();
+        assert_eq!(
+            alternatives,
+            [
+                (names[0].to_owned(), names[1].to_owned(), 5.0),
+                (names[2].to_owned(), names[3].to_owned(), 4.0)
+            ]
+        );
+    }
+}
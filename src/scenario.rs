@@ -0,0 +1,306 @@
+use rand::distributions::{Distribution as _, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::script::Script;
+use crate::simulator::Settings;
+
+/// A part of the starting state that isn't known exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Parameter {
+    /// Starting loyalty of Comet
+    Loyalty,
+    /// Life of the opponent, or of every opponent when there are several
+    Life,
+    /// Extra roll advantage effects
+    Advantage,
+    /// Mana available for paying costs during the turn
+    Mana,
+    /// Squirrels already on the battlefield that can attack this turn
+    Squirrels,
+}
+
+impl Parameter {
+    /// Sets the parameter of the settings to the value.
+    pub fn apply(&self, value: u32, settings: &mut Settings) {
+        let board = &mut settings.board;
+
+        match self {
+            Parameter::Loyalty => settings.loyalty = value as i32,
+            Parameter::Life if board.opponents.is_empty() => settings.damage = value,
+            Parameter::Life => board.opponents.iter_mut().for_each(|life| *life = value),
+            Parameter::Advantage => settings.advantage = value as usize,
+            Parameter::Mana => board.mana = value,
+            Parameter::Squirrels => board.squirrels = value,
+        }
+    }
+}
+
+impl FromStr for Parameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loyalty" => Ok(Parameter::Loyalty),
+            "life" => Ok(Parameter::Life),
+            "advantage" => Ok(Parameter::Advantage),
+            "mana" => Ok(Parameter::Mana),
+            "squirrels" => Ok(Parameter::Squirrels),
+            _ => Err(format!(
+                "unknown parameter \"{s}\", expected loyalty, life, advantage, mana or squirrels"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Parameter::Loyalty => "Loyalty",
+            Parameter::Life => "Life",
+            Parameter::Advantage => "Advantage",
+            Parameter::Mana => "Mana",
+            Parameter::Squirrels => "Squirrels",
+        };
+
+        f.pad(name)
+    }
+}
+
+/// Values a parameter takes, with their relative weights.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub values: Vec<(u32, f64)>,
+}
+
+impl Distribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> u32 {
+        let weights = WeightedIndex::new(self.values.iter().map(|(_, weight)| *weight))
+            .expect("distribution weights are validated when parsing");
+
+        self.values[weights.sample(rng)].0
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    /// Parses a single value like `5`, an inclusive uniform range like `4..7`, or a histogram of
+    /// values and their weights like `20:1,30:2,40:1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = if s.contains(':') {
+            s.split(',')
+                .map(|bar| {
+                    let (bar_value, weight) = bar
+                        .split_once(':')
+                        .ok_or_else(|| format!("invalid histogram bar \"{bar}\", expected <value>:<weight>"))?;
+                    let weight: f64 = weight
+                        .trim()
+                        .parse()
+                        .map_err(|err| format!("invalid weight \"{weight}\": {err}"))?;
+
+                    if !weight.is_finite() || weight < 0.0 {
+                        return Err(format!("invalid weight {weight}, expected a positive number"));
+                    }
+
+                    Ok((parse_value(bar_value)?, weight))
+                })
+                .collect::<Result<Vec<_>, String>>()?
+        } else {
+            parse_range(s)?.map(|value| (value, 1.0)).collect()
+        };
+
+        if values.iter().all(|(_, weight)| *weight == 0.0) {
+            return Err(format!("invalid distribution \"{s}\", every weight is zero"));
+        }
+
+        Ok(Distribution { values })
+    }
+}
+
+/// Parses a single value like `5`, or an inclusive range like `4..7` or `4..=7`.
+pub fn parse_range(s: &str) -> Result<RangeInclusive<u32>, String> {
+    match s.split_once("..") {
+        Some((from, to)) => {
+            let (from, to) = (parse_value(from)?, parse_value(to.trim_start_matches('='))?);
+            if from > to {
+                return Err(format!("invalid range \"{s}\", {from} is more than {to}"));
+            }
+
+            Ok(from..=to)
+        }
+        None => parse_value(s).map(|value| value..=value),
+    }
+}
+
+fn parse_value(value: &str) -> Result<u32, String> {
+    value
+        .trim()
+        .parse::<u32>()
+        .map_err(|err| format!("invalid value \"{value}\": {err}"))
+}
+
+/// A parameter sampled from a distribution for every game, like `loyalty=4..7`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Varied {
+    pub parameter: Parameter,
+    pub distribution: Distribution,
+}
+
+impl FromStr for Varied {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (parameter, distribution) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid parameter \"{s}\", expected <parameter>=<distribution>"))?;
+
+        Ok(Varied {
+            parameter: parameter.trim().parse()?,
+            distribution: distribution.parse()?,
+        })
+    }
+}
+
+/// The starting state of the game with the parameters that aren't known exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub settings: Settings,
+    pub varied: Vec<Varied>,
+}
+
+impl Scenario {
+    pub fn new(settings: &Settings, varied: &[Varied]) -> Self {
+        Self {
+            settings: settings.clone(),
+            varied: varied.to_vec(),
+        }
+    }
+
+    /// Settings for a single game with every varied parameter sampled, and the sampled values in
+    /// the order the parameters were given.
+    pub fn sample(&self, rng: &mut impl Rng) -> (Settings, Vec<u32>) {
+        let mut settings = self.settings.clone();
+        let values = self
+            .varied
+            .iter()
+            .map(|varied| {
+                let value = varied.distribution.sample(rng);
+                varied.parameter.apply(value, &mut settings);
+                value
+            })
+            .collect();
+
+        (settings, values)
+    }
+}
+
+/// A scenario file, adding custom card scripts and varied parameters to the starting state given
+/// by the options.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScenarioFile {
+    pub scripts: Vec<Script>,
+    pub varied: Vec<Varied>,
+}
+
+impl FromStr for ScenarioFile {
+    type Err = String;
+
+    /// Parses a scenario from JSON, with the scripts given by their name and Rhai source and the
+    /// varied parameters like `--vary`:
+    /// `{"scripts": [{"name": "Lucky", "source": "fn modify_roll(roll) { roll + 1 }"}], "vary": ["loyalty=4..7"]}`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[derive(Default, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        struct Json {
+            scripts: Vec<Script>,
+            vary: Vec<String>,
+        }
+
+        let json: Json = serde_json::from_str(s).map_err(|err| format!("invalid scenario: {err}"))?;
+
+        Ok(ScenarioFile {
+            scripts: json.scripts,
+            varied: json
+                .vary
+                .iter()
+                .map(|varied| varied.parse())
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn parses_a_single_value() {
+        let distribution: Distribution = "5".parse().unwrap();
+        assert_eq!(distribution.values, vec![(5, 1.0)]);
+    }
+
+    #[test]
+    fn parses_an_inclusive_range() {
+        let distribution: Distribution = "4..7".parse().unwrap();
+        assert_eq!(distribution.values, vec![(4, 1.0), (5, 1.0), (6, 1.0), (7, 1.0)]);
+        assert_eq!("4..=7".parse::<Distribution>().unwrap(), distribution);
+    }
+
+    #[test]
+    fn parses_a_histogram() {
+        let distribution: Distribution = "20:1, 30:2,40:0.5".parse().unwrap();
+        assert_eq!(distribution.values, vec![(20, 1.0), (30, 2.0), (40, 0.5)]);
+    }
+
+    #[test]
+    fn rejects_invalid_distributions() {
+        for invalid in ["", "7..4", "a..b", "20:-1", "20:0,30:0", "20:1,30", "20:inf"] {
+            assert!(invalid.parse::<Distribution>().is_err(), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn samples_only_weighted_values() {
+        let distribution: Distribution = "1:0,2:1,3:0".parse().unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!((0..100).all(|_| distribution.sample(&mut rng) == 2));
+    }
+
+    #[test]
+    fn parses_a_scenario_file() {
+        let scenario: ScenarioFile = r#"{
+            "scripts": [{"name": "Lucky", "source": "fn modify_roll(roll) { roll + 1 }"}],
+            "vary": ["loyalty=4..5", "life=20:1,30:2"]
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(scenario.scripts.len(), 1);
+        assert!(scenario.scripts[0].has_hook("modify_roll"));
+        assert_eq!(
+            scenario.varied.iter().map(|varied| varied.parameter).collect::<Vec<_>>(),
+            vec![Parameter::Loyalty, Parameter::Life]
+        );
+
+        assert_eq!("{}".parse::<ScenarioFile>().unwrap(), ScenarioFile::default());
+        assert!(r#"{"vary": ["colour=4"]}"#.parse::<ScenarioFile>().is_err());
+        assert!(r#"{"scripts": [{"name": "Broken", "source": "fn ("}]}"#.parse::<ScenarioFile>().is_err());
+        assert!(r#"{"loyalty": 4}"#.parse::<ScenarioFile>().is_err());
+    }
+
+    #[test]
+    fn parses_a_varied_parameter() {
+        let varied: Varied = "loyalty=4..5".parse().unwrap();
+        assert_eq!(varied.parameter, Parameter::Loyalty);
+        assert_eq!(varied.distribution.values, vec![(4, 1.0), (5, 1.0)]);
+
+        assert!("loyalty".parse::<Varied>().is_err());
+        assert!("colour=4".parse::<Varied>().is_err());
+    }
+}
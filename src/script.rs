@@ -0,0 +1,242 @@
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Operations a single hook can run before it is stopped, so that a script can't hang a simulation.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+/// Damage, tokens, mana or loyalty a single hook call can add, so that the game can't overflow.
+const MAX_AMOUNT: u32 = 1_000;
+/// Activations a single hook call can add.
+const MAX_ACTIVATIONS: u32 = 10;
+/// Roll advantage a single hook call can add, so that a roll can't take forever.
+const MAX_ROLL_ADVANTAGE: u32 = 10;
+
+/// Hooks a script can define, all of them optional.
+pub const HOOKS: [&str; 5] = [
+    "on_activate",
+    "modify_roll",
+    "on_roll",
+    "replace_damage",
+    "replace_tokens",
+];
+
+/// What the scripts see of the game, and the actions they take through it.
+///
+/// The hooks are called with the game as `this`, so a script reads `this.loyalty` and deals
+/// damage with `this.deal_damage(2)`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptGame {
+    pub loyalty: i64,
+    pub activations: i64,
+    pub damage: i64,
+    pub squirrels: i64,
+    pub mana: i64,
+    pub roll_advantage: i64,
+    pub target_damage: i64,
+    pub actions: Actions,
+}
+
+/// Actions taken by a script, applied to the game once the hook returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Actions {
+    pub damage: u32,
+    pub squirrels: u32,
+    pub loyalty: i32,
+    pub activations: u32,
+    pub mana: u32,
+    pub roll_advantage: u32,
+}
+
+impl ScriptGame {
+    fn deal_damage(&mut self, amount: i64) {
+        self.actions.damage = add(self.actions.damage, amount, MAX_AMOUNT);
+    }
+
+    fn create_squirrels(&mut self, amount: i64) {
+        self.actions.squirrels = add(self.actions.squirrels, amount, MAX_AMOUNT);
+    }
+
+    fn add_loyalty(&mut self, amount: i64) {
+        let max = MAX_AMOUNT as i64;
+        self.actions.loyalty = (self.actions.loyalty as i64).saturating_add(amount).clamp(-max, max) as i32;
+    }
+
+    fn add_activations(&mut self, amount: i64) {
+        self.actions.activations = add(self.actions.activations, amount, MAX_ACTIVATIONS);
+    }
+
+    fn add_mana(&mut self, amount: i64) {
+        self.actions.mana = add(self.actions.mana, amount, MAX_AMOUNT);
+    }
+
+    fn add_roll_advantage(&mut self, amount: i64) {
+        self.actions.roll_advantage = add(self.actions.roll_advantage, amount, MAX_ROLL_ADVANTAGE);
+    }
+}
+
+/// Adds a positive amount from a script to an action, up to `max` for a single hook call.
+fn add(action: u32, amount: i64, max: u32) -> u32 {
+    (action as i64).saturating_add(amount.max(0)).min(max as i64) as u32
+}
+
+/// The amount a replacement hook replaced `amount` with, no more than `MAX_AMOUNT` unless the
+/// amount already was.
+pub fn replaced(amount: u32, replacement: i64) -> u32 {
+    replacement.clamp(0, amount.max(MAX_AMOUNT) as i64) as u32
+}
+
+/// A compiled Rhai script with custom card effects, loaded from a script file.
+///
+/// The scripts run sandboxed: they can't reach the file system or the network, every hook call
+/// is stopped after `MAX_OPERATIONS` operations, and the actions and replacements of a single call
+/// are capped by `MAX_AMOUNT`, `MAX_ACTIVATIONS` and `MAX_ROLL_ADVANTAGE`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ScriptSource", into = "ScriptSource")]
+pub struct Script {
+    name: String,
+    source: String,
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    hooks: Vec<&'static str>,
+    failed: Arc<AtomicBool>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ScriptSource {
+    name: String,
+    source: String,
+}
+
+impl Script {
+    pub fn new(name: &str, source: &str) -> Result<Self, String> {
+        let engine = engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|err| format!("failed to compile script {name}: {err}"))?;
+
+        let hooks = HOOKS
+            .iter()
+            .copied()
+            .filter(|hook| ast.iter_functions().any(|function| function.name == *hook))
+            .collect();
+
+        Ok(Self {
+            name: name.to_owned(),
+            source: source.to_owned(),
+            engine: Arc::new(engine),
+            ast: Arc::new(ast),
+            hooks,
+            failed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_hook(&self, hook: &str) -> bool {
+        self.hooks.contains(&hook)
+    }
+
+    /// Calls the hook with the game as `this`, returning the result and the actions taken.
+    /// Failing hooks are treated as if they weren't defined, warning only about the first failure
+    /// of the script.
+    pub fn call<T: Clone + Send + Sync + 'static>(
+        &self,
+        hook: &str,
+        game: ScriptGame,
+        args: impl FuncArgs,
+    ) -> Option<(T, Actions)> {
+        if !self.has_hook(hook) {
+            return None;
+        }
+
+        let mut this = Dynamic::from(game);
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+        let result = self
+            .engine
+            .call_fn_with_options::<T>(options, &mut Scope::new(), &self.ast, hook, args);
+
+        match result {
+            Ok(value) => {
+                let actions = this
+                    .try_cast::<ScriptGame>()
+                    .map(|game| game.actions)
+                    .unwrap_or_default();
+                Some((value, actions))
+            }
+            Err(err) => {
+                if self.failed.swap(true, Ordering::Relaxed) {
+                    log::debug!("Script {} failed in {hook}: {err}", self.name);
+                } else {
+                    log::warn!("Script {} failed in {hook}: {err}", self.name);
+                }
+                None
+            }
+        }
+    }
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_max_string_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval")
+        .on_print(|text| log::debug!("{text}"))
+        .on_debug(|text, _, _| log::debug!("{text}"));
+
+    engine
+        .register_type_with_name::<ScriptGame>("Game")
+        .register_get("loyalty", |game: &mut ScriptGame| game.loyalty)
+        .register_get("activations", |game: &mut ScriptGame| game.activations)
+        .register_get("damage", |game: &mut ScriptGame| game.damage)
+        .register_get("squirrels", |game: &mut ScriptGame| game.squirrels)
+        .register_get("mana", |game: &mut ScriptGame| game.mana)
+        .register_get("roll_advantage", |game: &mut ScriptGame| game.roll_advantage)
+        .register_get("target_damage", |game: &mut ScriptGame| game.target_damage)
+        .register_fn("deal_damage", ScriptGame::deal_damage)
+        .register_fn("create_squirrels", ScriptGame::create_squirrels)
+        .register_fn("add_loyalty", ScriptGame::add_loyalty)
+        .register_fn("add_activations", ScriptGame::add_activations)
+        .register_fn("add_mana", ScriptGame::add_mana)
+        .register_fn("add_roll_advantage", ScriptGame::add_roll_advantage);
+
+    engine
+}
+
+impl TryFrom<ScriptSource> for Script {
+    type Error = String;
+
+    fn try_from(script: ScriptSource) -> Result<Self, Self::Error> {
+        Script::new(&script.name, &script.source)
+    }
+}
+
+impl From<Script> for ScriptSource {
+    fn from(script: Script) -> Self {
+        ScriptSource {
+            name: script.name,
+            source: script.source,
+        }
+    }
+}
+
+impl PartialEq for Script {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.source == other.source
+    }
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Script").field("name", &self.name).finish()
+    }
+}
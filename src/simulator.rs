@@ -8,27 +8,43 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::WorkerGlobalScope;
 
-use crate::game::{Game, GameResult};
+use crate::game::{Board, Game, GameResult};
 
 const MAX_BATCH_SIZE: usize = 10000;
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Should combat damage using squirrels be included
+    pub squirrels: bool,
+
+    /// Extra roll advantage effects
+    pub advantage: usize,
+
+    /// Starting loyalty of the planeswalker
+    pub loyalty: i32,
+
+    /// Damage required to win for win
+    pub damage: u32,
+
+    /// Permanents modifying the activation results
+    pub board: Board,
+}
+
+impl Settings {
+    pub fn game(&self) -> Game {
+        Game::new(self.squirrels, self.advantage, self.loyalty, self.damage)
+            .with_board(self.board.clone())
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum Cmd {
     Begin {
         /// Total simulations to simulate
         simulations: usize,
 
-        /// Should combat damage using squirrels be included
-        squirrels: bool,
-
-        /// Extra roll advantage effects
-        advantage: usize,
-
-        /// Starting loyalty of the planeswalker
-        loyalty: i32,
-
-        /// Damage required to win for win
-        damage: u32,
+        /// Game settings shared by every simulation
+        settings: Box<Settings>,
     },
     Cancel,
 }
@@ -78,10 +94,7 @@ impl Simulator {
         scope: WorkerScope<Self>,
         id: HandlerId,
         simulations: usize,
-        squirrels: bool,
-        advantage: usize,
-        loyalty: i32,
-        damage: u32,
+        settings: Settings,
     ) {
         {
             let mut state = state.lock().unwrap();
@@ -115,7 +128,7 @@ impl Simulator {
 
             progress += batch_size;
 
-            match Simulator::run_batch(batch_size, squirrels, advantage, loyalty, damage) {
+            match Simulator::run_batch(batch_size, &settings) {
                 Ok(results) => {
                     if progress == simulations {
                         scope.respond(id, Status::Complete(simulations, results));
@@ -137,15 +150,12 @@ impl Simulator {
 
     fn run_batch(
         batch_size: usize,
-        squirrels: bool,
-        advantage: usize,
-        loyalty: i32,
-        damage: u32,
+        settings: &Settings,
     ) -> Result<Vec<GameResult>, Box<dyn Error>> {
         let mut results = Vec::new();
 
         for _ in 0..batch_size {
-            let mut game = Game::new(squirrels, advantage, loyalty, damage);
+            let mut game = settings.game();
             let result = game.run();
             results.push(result);
         }
@@ -172,18 +182,12 @@ impl Worker for Simulator {
             Msg::Command { cmd, id } => match cmd {
                 Cmd::Begin {
                     simulations,
-                    squirrels,
-                    advantage,
-                    loyalty,
-                    damage,
+                    settings,
                 } => {
                     let (state, scope) = (Arc::clone(&self.state), scope.clone());
 
                     spawn_local(async move {
-                        Simulator::run(
-                            state, scope, id, simulations, squirrels, advantage, loyalty, damage,
-                        )
-                        .await;
+                        Simulator::run(state, scope, id, simulations, *settings).await;
                     });
                 }
                 Cmd::Cancel => {
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use crate::ability::Ability;
+use crate::combat::CombatTiming;
+use crate::game::Game;
+use crate::interaction::Interaction;
+use crate::strategy::Strategy;
+
+/// Sweeps are stopped once no state value changes more than this.
+const CONVERGENCE: f64 = 1e-10;
+const MAX_SWEEPS: usize = 10000;
+
+/// Activations beyond this are treated as if there were exactly this many left.
+const MAX_ACTIVATIONS: u32 = 24;
+
+/// How the dice are kept while solving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Keep the die maximising the odds of winning
+    Optimal,
+    /// Always keep the highest roll
+    Naive,
+}
+
+/// Win probabilities for every state of Comet's activation chain, found with value iteration.
+///
+/// The state is Comet's loyalty, the free activations left and the damage dealt so far, including
+/// the squirrel attack if squirrels are included. Mana, copies, returned cards and the opponent's
+/// blockers aren't part of the state, so those decisions are left for the defaults and every
+/// squirrel is assumed to connect. Several opponents are solved as one with their combined life,
+/// and with several Comets only the one being activated is considered.
+#[derive(Debug)]
+pub struct Solution {
+    policy: Policy,
+    dice: usize,
+    target: u32,
+    max_loyalty: i32,
+    squirrel_progress: u32,
+    roll_progress: u32,
+    comet_damage: Vec<u32>,
+    values: Vec<f64>,
+}
+
+impl Solution {
+    /// Solves the chain for the board and starting state of the game.
+    pub fn new(game: &Game, policy: Policy) -> Self {
+        let target = game.target_damage();
+        let max_loyalty = i32::max(game.loyalty(), target as i32) + 4;
+
+        let squirrel_progress = if game.include_squirrels()
+            && game.board().combat_timing == CombatTiming::BeforeCombat
+        {
+            game.create_tokens(2) * game.squirrel_power()
+        } else {
+            0
+        };
+
+        let mut solution = Self {
+            policy,
+            dice: 1 + game.roll_advantage(),
+            target,
+            max_loyalty,
+            squirrel_progress,
+            roll_progress: game.replace_damage(game.damage_on_roll(), true),
+            comet_damage: (0..=max_loyalty)
+                .map(|loyalty| game.replace_damage(loyalty as u32, true))
+                .collect(),
+            values: vec![0.0; (max_loyalty as usize + 1) * (MAX_ACTIVATIONS as usize + 1) * (target as usize + 1)],
+        };
+
+        solution.iterate();
+        solution
+    }
+
+    /// The solver knows only the results of Comet's own ability.
+    pub fn supports(game: &Game) -> Result<(), String> {
+        if game.board().ability == Ability::comet() {
+            Ok(())
+        } else {
+            Err(format!("the solver doesn't support {}", game.board().ability.name))
+        }
+    }
+
+    /// Is the solved value exact for the game, with nothing on the board the solver leaves out of
+    /// the state.
+    pub fn is_exact(game: &Game) -> bool {
+        let board = game.board();
+
+        Solution::supports(game).is_ok()
+            && board.paid_activations.is_empty()
+            && board.copy_effects.is_empty()
+            && board.graveyard.is_empty()
+            && board.blockers.is_empty()
+            && board.sacrifice_outlets.is_empty()
+            && board.extra_comets.is_empty()
+            && board.opponents.len() <= 1
+            && board.interaction == Interaction::default()
+            && board.attractions.attractions.is_empty()
+            && board.scripts.is_empty()
+    }
+
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    pub fn max_loyalty(&self) -> i32 {
+        self.max_loyalty
+    }
+
+    /// Probability of winning from the state, using the solved policy.
+    pub fn value(&self, loyalty: i32, activations: u32, damage: u32) -> f64 {
+        if damage >= self.target {
+            return 1.0;
+        }
+
+        if loyalty <= 0 || activations == 0 {
+            return 0.0;
+        }
+
+        self.values[self.index(loyalty, activations, damage)]
+    }
+
+    /// Probability of winning after keeping each of the faces 1 to 6, when the roll happens with
+    /// `activations` left after this activation.
+    pub fn face_values(&self, loyalty: i32, activations: u32, damage: u32) -> [f64; 6] {
+        let damage = damage + self.roll_progress;
+        let squirrels = self.value(loyalty + 2, activations, damage + self.squirrel_progress);
+        let returns = self.value(loyalty - 1, activations, damage);
+        let comet_damage = self.comet_damage(loyalty, activations, damage);
+        let extra_activations = self.value(loyalty + 1, activations + 2, damage);
+
+        [
+            squirrels,
+            squirrels,
+            returns,
+            comet_damage,
+            comet_damage,
+            extra_activations,
+        ]
+    }
+
+    /// Probability of winning after the 4 or 5 result hits the opponent.
+    fn comet_damage(&self, loyalty: i32, activations: u32, damage: u32) -> f64 {
+        let dealt = self.comet_damage[loyalty.clamp(0, self.max_loyalty) as usize];
+        self.value(loyalty - 2, activations, damage + dealt)
+    }
+
+    /// Faces from 1 to 6 ordered from the best to keep to the worst.
+    pub fn preference(&self, loyalty: i32, activations: u32, damage: u32) -> [u32; 6] {
+        let mut faces = [6, 5, 4, 3, 2, 1];
+        if self.policy == Policy::Optimal {
+            let values = self.face_values(loyalty, activations, damage);
+            faces.sort_by(|a, b| values[*b as usize - 1].total_cmp(&values[*a as usize - 1]));
+        }
+
+        faces
+    }
+
+    fn index(&self, loyalty: i32, activations: u32, damage: u32) -> usize {
+        let loyalty = loyalty.min(self.max_loyalty) as usize;
+        let activations = activations.min(MAX_ACTIVATIONS) as usize;
+
+        (loyalty * (MAX_ACTIVATIONS as usize + 1) + activations) * (self.target as usize + 1)
+            + damage as usize
+    }
+
+    fn iterate(&mut self) {
+        for sweep in 0..MAX_SWEEPS {
+            let mut delta: f64 = 0.0;
+
+            for loyalty in 1..=self.max_loyalty {
+                for activations in 1..=MAX_ACTIVATIONS {
+                    for damage in 0..self.target {
+                        let value = self.activate(loyalty, activations, damage);
+                        let index = self.index(loyalty, activations, damage);
+                        delta = delta.max((value - self.values[index]).abs());
+                        self.values[index] = value;
+                    }
+                }
+            }
+
+            if delta < CONVERGENCE {
+                log::debug!("Value iteration converged after {} sweeps.", sweep + 1);
+                return;
+            }
+        }
+
+        log::warn!("Value iteration did not converge in {MAX_SWEEPS} sweeps.");
+    }
+
+    /// Expected value of activating once more, keeping the best die out of all rolled dice.
+    fn activate(&self, loyalty: i32, activations: u32, damage: u32) -> f64 {
+        let values = self.face_values(loyalty, activations - 1, damage);
+        let faces = self.preference(loyalty, activations - 1, damage);
+
+        // The best face among the dice is the kept one, so the n:th preferred face is kept when
+        // none of the dice show a more preferred face and at least one of them shows this face
+        let dice = self.dice as i32;
+        faces
+            .iter()
+            .enumerate()
+            .map(|(rank, face)| {
+                let allowed = (6 - rank) as f64 / 6.0;
+                let worse = (5 - rank) as f64 / 6.0;
+                (allowed.powi(dice) - worse.powi(dice)) * values[*face as usize - 1]
+            })
+            .sum()
+    }
+}
+
+/// Plays the decisions of a solved `Solution`, falling back to the defaults for the rest.
+#[derive(Debug, Clone)]
+pub struct OptimalStrategy {
+    solution: Arc<Solution>,
+}
+
+impl OptimalStrategy {
+    pub fn new(game: &Game) -> Self {
+        Self {
+            solution: Arc::new(Solution::new(game, Policy::Optimal)),
+        }
+    }
+}
+
+impl Strategy for OptimalStrategy {
+    fn choose_roll(&self, game: &Game, rolls: &[u32]) -> usize {
+        let values = self.solution.face_values(
+            game.loyalty(),
+            game.free_activations_left(),
+            game.total_damage(),
+        );
+
+        rolls
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| values[**a as usize - 1].total_cmp(&values[**b as usize - 1]))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    fn keep_activating(&self, game: &Game) -> bool {
+        !game.is_lethal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Board, Outcome};
+    use crate::simulator::Settings;
+
+    fn settings(advantage: usize, damage: u32) -> Settings {
+        Settings {
+            squirrels: true,
+            advantage,
+            loyalty: 5,
+            damage,
+            board: Board::default(),
+        }
+    }
+
+    fn solve(settings: &Settings, policy: Policy) -> f64 {
+        let game = settings.game();
+        Solution::new(&game, policy).value(
+            game.loyalty(),
+            game.free_activations_left(),
+            game.total_damage(),
+        )
+    }
+
+    #[test]
+    fn naive_solution_matches_the_simulation() {
+        let settings = settings(3, 40);
+        let solved = solve(&settings, Policy::Naive);
+
+        let games = 20_000;
+        let wins = (0..games)
+            .filter(|seed| matches!(settings.game().with_seed(*seed).run().outcome, Outcome::Win))
+            .count();
+        let simulated = wins as f64 / games as f64;
+
+        let standard_error = (solved * (1.0 - solved) / games as f64).sqrt();
+        assert!(
+            (solved - simulated).abs() < 4.0 * standard_error,
+            "solved {solved:.4}, simulated {simulated:.4}"
+        );
+    }
+
+    #[test]
+    fn optimal_play_wins_at_least_as_often_as_naive_play() {
+        for advantage in 0..3 {
+            let settings = settings(advantage, 20);
+            assert!(solve(&settings, Policy::Optimal) >= solve(&settings, Policy::Naive) - CONVERGENCE);
+        }
+    }
+
+    #[test]
+    fn dealt_damage_is_a_win() {
+        let solution = Solution::new(&settings(0, 20).game(), Policy::Optimal);
+        assert_eq!(solution.value(5, 1, 20), 1.0);
+    }
+
+    #[test]
+    fn other_abilities_are_not_supported() {
+        let mut settings = settings(0, 20);
+        settings.board.ability = Ability::preset("delina").unwrap();
+        assert!(Solution::supports(&settings.game()).is_err());
+        assert!(!Solution::is_exact(&settings.game()));
+    }
+}
@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::card::{Card, CardEffect};
+use crate::combat::{self, CombatTiming};
+use crate::game::{Game, SacrificeOutlet};
+use crate::solver::{OptimalStrategy, Solution};
+
+/// Where Comet's "deals damage to a creature or player" result is aimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageTarget {
+    /// One of the opponents, by its index in `Game::opponents`
+    Opponent(usize),
+    /// One of the opponent's blockers, by its index in `Game::blockers`
+    Creature(usize),
+}
+
+impl Display for DamageTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DamageTarget::Opponent(index) => write!(f, "Opponent {}", index + 1),
+            DamageTarget::Creature(index) => write!(f, "Creature {}", index + 1),
+        }
+    }
+}
+
+/// The decisions a player makes while activating Comet, Stellar Pup.
+///
+/// Every decision has a default implementation that matches how the simulator has always played,
+/// so custom strategies only need to override the decisions they care about.
+pub trait Strategy: Debug + Send + Sync {
+    /// Picks the index of the die to keep out of the rolled dice.
+    fn choose_roll(&self, _game: &Game, rolls: &[u32]) -> usize {
+        rolls
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, roll)| **roll)
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    /// Picks the index of the Comet to activate next out of the candidates, by default the one
+    /// with the most loyalty that still has free activations left.
+    fn choose_comet(&self, game: &Game, candidates: &[usize]) -> usize {
+        candidates
+            .iter()
+            .copied()
+            .max_by_key(|index| {
+                let comet = &game.comets()[*index];
+                (comet.free_activations_left() > 0, comet.loyalty, Reverse(*index))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Picks the index of the die to keep out of the dice rolled to visit the Attractions, by
+    /// default the one visiting the most open Attractions.
+    fn choose_visit(&self, game: &Game, rolls: &[u32]) -> usize {
+        rolls
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, roll)| {
+                game.open_attractions()
+                    .filter(|attraction| attraction.is_lit(**roll))
+                    .count()
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    /// Should Comet be activated again, assuming there is an activation available.
+    fn keep_activating(&self, _game: &Game) -> bool {
+        true
+    }
+
+    /// Where the damage from the 4 or 5 result goes.
+    fn damage_target(&self, game: &Game, damage: u32) -> DamageTarget {
+        DamageTarget::Opponent(self.opponent_target(game, damage))
+    }
+
+    /// Picks the index of the opponent that damage dealt to a player goes to, by default the
+    /// opponent closest to being eliminated.
+    fn opponent_target(&self, game: &Game, _damage: u32) -> usize {
+        game.life_left()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, life)| (**life == 0, **life))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    /// Splits the attacking squirrels between the opponents, by default sending all of them at
+    /// the opponent closest to being eliminated.
+    fn attack(&self, game: &Game, attackers: u32) -> Vec<u32> {
+        let mut assigned = vec![0; game.opponents().len()];
+        if let Some(count) = assigned.get_mut(self.opponent_target(game, 0)) {
+            *count = attackers;
+        }
+
+        assigned
+    }
+
+    /// Should the mana be paid to copy the ability that was just activated.
+    fn copy_ability(&self, game: &Game, cost: u32) -> bool {
+        game.spare_mana() >= cost
+    }
+
+    /// Should the mana be paid for one more activation this turn.
+    fn pay_for_activation(&self, game: &Game, cost: u32) -> bool {
+        game.mana() >= cost
+    }
+
+    /// Picks the index of the card to return from the graveyard, if any.
+    fn return_card(&self, game: &Game, graveyard: &[Card]) -> Option<usize> {
+        let spare_mana = game.spare_mana();
+        graveyard
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, card)| (card.mana_value <= spare_mana, card.value()))
+            .map(|(index, _)| index)
+    }
+
+    /// Should the card in hand be cast now.
+    fn cast_card(&self, game: &Game, card: &Card) -> bool {
+        card.effect != CardEffect::None && card.mana_value <= game.spare_mana()
+    }
+
+    /// How many squirrels to sacrifice to the outlet now, either right after an activation
+    /// resolves or once combat damage has been dealt.
+    ///
+    /// By default the squirrels that could still attack are kept for combat, and all the others go
+    /// to the outlet dealing the most damage, or to scrying if none of the outlets deal damage.
+    fn sacrifice(&self, game: &Game, outlet: &SacrificeOutlet, after_combat: bool) -> u32 {
+        let can_attack = game.include_squirrels()
+            && game.board().combat_timing == CombatTiming::BeforeCombat;
+        if can_attack && !after_combat {
+            return 0;
+        }
+
+        let best = game
+            .board()
+            .sacrifice_outlets
+            .iter()
+            .map(|outlet| (outlet.damage, outlet.scry))
+            .max()
+            .unwrap_or_default();
+
+        if (outlet.damage, outlet.scry) == best {
+            game.squirrels_on_battlefield()
+        } else {
+            0
+        }
+    }
+}
+
+/// Always keeps the highest roll, uses every activation and sends all damage to the opponent.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultStrategy;
+
+impl Strategy for DefaultStrategy {}
+
+/// Names of the built-in strategies `built_in` accepts.
+pub const BUILT_IN: &str = "default, optimal, blockers, never, lethal, loyalty:<N> or survival";
+
+/// Looks up a built-in strategy by name: default, optimal, or one of the stop policies.
+///
+/// The optimal strategy is solved for the board and starting state of the game.
+pub fn built_in(name: &str, game: &Game) -> Result<Arc<dyn Strategy>, String> {
+    match name {
+        "default" => Ok(Arc::new(DefaultStrategy)),
+        "optimal" => {
+            Solution::supports(game)?;
+            Ok(Arc::new(OptimalStrategy::new(game)))
+        }
+        "blockers" => Ok(Arc::new(ClearBlockers)),
+        _ => name
+            .parse::<StopPolicy>()
+            .map(|policy| Arc::new(policy) as Arc<dyn Strategy>)
+            .map_err(|_| format!("unknown strategy \"{name}\", expected {BUILT_IN}")),
+    }
+}
+
+/// Shoots a blocker instead of the opponent when the squirrels it lets through deal more damage,
+/// or when that makes the squirrel attack lethal.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClearBlockers;
+
+impl Strategy for ClearBlockers {
+    fn damage_target(&self, game: &Game, damage: u32) -> DamageTarget {
+        if !game.include_squirrels() || game.board().combat_timing == CombatTiming::AfterCombat {
+            return DamageTarget::Opponent(self.opponent_target(game, damage));
+        }
+
+        let smallest = game
+            .blockers()
+            .iter()
+            .enumerate()
+            .filter(|(_, toughness)| **toughness <= damage)
+            .min_by_key(|(_, toughness)| **toughness);
+
+        let Some((index, _)) = smallest else {
+            return DamageTarget::Opponent(self.opponent_target(game, damage));
+        };
+
+        let blockers = game.combat_blockers();
+        let before = combat::resolve(game.attackers(), game.squirrel_power(), blockers);
+        let after = combat::resolve(game.attackers(), game.squirrel_power(), blockers - 1);
+        let gained = after.damage - before.damage;
+
+        let face_lethal = game.total_damage() + damage >= game.target_damage();
+        let creature_lethal = game.total_damage() + gained >= game.target_damage();
+
+        if gained > damage || creature_lethal && !face_lethal {
+            DamageTarget::Creature(index)
+        } else {
+            DamageTarget::Opponent(self.opponent_target(game, damage))
+        }
+    }
+}
+
+/// When to decline the remaining activations instead of always activating while possible.
+///
+/// All of the policies except `Never` stop once lethal damage has been dealt, as activating
+/// further can only put Comet at risk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopPolicy {
+    /// Use every activation
+    #[default]
+    Never,
+    /// Stop once lethal damage has been dealt
+    AtLethal,
+    /// Also stop when Comet's loyalty is below the threshold
+    BelowLoyalty(i32),
+    /// Also stop when the next activation could kill Comet, which maximises the odds of Comet
+    /// surviving the turn as declining an activation never risks any loyalty
+    Survival,
+}
+
+impl Strategy for StopPolicy {
+    fn keep_activating(&self, game: &Game) -> bool {
+        match self {
+            StopPolicy::Never => true,
+            StopPolicy::AtLethal => !game.is_lethal(),
+            StopPolicy::BelowLoyalty(threshold) => !game.is_lethal() && game.loyalty() >= *threshold,
+            StopPolicy::Survival => {
+                !game.is_lethal() && game.loyalty() + game.worst_loyalty_change() > 0
+            }
+        }
+    }
+}
+
+impl FromStr for StopPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("loyalty", threshold)) => threshold
+                .parse()
+                .map(StopPolicy::BelowLoyalty)
+                .map_err(|err| format!("invalid loyalty threshold \"{threshold}\": {err}")),
+            _ => match s {
+                "never" => Ok(StopPolicy::Never),
+                "lethal" => Ok(StopPolicy::AtLethal),
+                "survival" => Ok(StopPolicy::Survival),
+                _ => Err(format!(
+                    "unknown stop policy \"{s}\", expected never, lethal, loyalty:<N> or survival"
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::{Ability, Band, Effect};
+    use crate::game::{Board, GameResult};
+    use crate::simulator::Settings;
+
+    /// Settings with an ability rolling a one-sided die, so that every activation has the same
+    /// effects, starting at 5 loyalty.
+    fn settings(effects: Vec<Effect>, damage: u32, board: Board) -> Settings {
+        let ability = Ability {
+            name: "Fixed".to_owned(),
+            red: false,
+            die: 1,
+            bands: vec![Band { from: 1, to: 1, effects }],
+        };
+
+        Settings {
+            squirrels: true,
+            advantage: 0,
+            loyalty: 5,
+            damage,
+            board: Board { ability, ..board },
+        }
+    }
+
+    /// Plays a game where every activation deals 10 damage and loses 2 loyalty.
+    fn play(policy: StopPolicy, damage: u32) -> GameResult {
+        let effects = vec![Effect::Damage(10), Effect::Loyalty(-2), Effect::ExtraActivations(1)];
+        settings(effects, damage, Board::default())
+            .game()
+            .with_strategy(Arc::new(policy))
+            .with_seed(1)
+            .run()
+    }
+
+    #[test]
+    fn stop_policies_decline_the_remaining_activations() {
+        let stopped = |policy: StopPolicy, damage: u32| {
+            let result = play(policy, damage);
+            (result.loyalty, result.damage)
+        };
+
+        assert_eq!(stopped(StopPolicy::Never, 20), (-1, 30));
+        assert_eq!(stopped(StopPolicy::AtLethal, 20), (1, 20));
+        assert_eq!(stopped(StopPolicy::AtLethal, 100), (-1, 30));
+        assert_eq!(stopped(StopPolicy::BelowLoyalty(4), 100), (3, 10));
+        assert_eq!(stopped(StopPolicy::Survival, 100), (1, 20));
+    }
+
+    #[test]
+    fn clearing_a_blocker_lets_the_squirrels_deal_more_damage() {
+        let result = |strategy: Arc<dyn Strategy>, combat_timing: CombatTiming| {
+            let board = Board {
+                blockers: vec![1],
+                squirrel_anthems: 2,
+                combat_timing,
+                ..Board::default()
+            };
+            let effects = vec![Effect::Squirrels(2), Effect::Damage(1), Effect::Loyalty(-5)];
+            settings(effects, 100, board)
+                .game()
+                .with_strategy(strategy)
+                .with_seed(1)
+                .run()
+        };
+
+        // Killing the blocker lets a 3/3 squirrel through instead of dealing 1 damage
+        let cleared = result(Arc::new(ClearBlockers), CombatTiming::BeforeCombat);
+        assert_eq!((cleared.blockers_killed, cleared.damage), (1, 0));
+        assert_eq!((cleared.blocked, cleared.squirrel_damage), (0, 6));
+
+        let default = result(Arc::new(DefaultStrategy), CombatTiming::BeforeCombat);
+        assert_eq!((default.blockers_killed, default.damage), (0, 1));
+        assert_eq!((default.blocked, default.squirrel_damage), (1, 3));
+
+        // After combat the blocker has already blocked
+        let after = result(Arc::new(ClearBlockers), CombatTiming::AfterCombat);
+        assert_eq!((after.blockers_killed, after.damage), (0, 1));
+    }
+
+    #[test]
+    fn parses_the_stop_policies() {
+        assert_eq!("lethal".parse(), Ok(StopPolicy::AtLethal));
+        assert_eq!("loyalty:3".parse(), Ok(StopPolicy::BelowLoyalty(3)));
+        assert_eq!("survival".parse(), Ok(StopPolicy::Survival));
+        assert!("loyalty:x".parse::<StopPolicy>().is_err());
+        assert!("sometimes".parse::<StopPolicy>().is_err());
+    }
+}
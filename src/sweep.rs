@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::game::{GameResult, Outcome};
+use crate::scenario::{parse_range, Parameter};
+use crate::simulator::Settings;
+
+/// Values of a swept parameter, like `0..20`, `20,40` or `0..10,15,20`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Axis {
+    pub values: Vec<u32>,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    /// Parses comma separated values and inclusive ranges.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = Vec::new();
+        for part in s.split(',') {
+            values.extend(parse_range(part)?);
+        }
+
+        Ok(Axis { values })
+    }
+}
+
+/// Totals of simulated games, merged from the games of every thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    pub games: usize,
+    pub wins: usize,
+    pub damage: u64,
+    pub squirrels: u64,
+    pub returns: u64,
+    pub rolls: u64,
+}
+
+impl Summary {
+    pub fn record(mut self, result: &GameResult) -> Self {
+        self.games += 1;
+        if let Outcome::Win = result.outcome {
+            self.wins += 1;
+        }
+        self.damage += result.damage as u64;
+        self.squirrels += result.squirrels as u64;
+        self.returns += result.returns as u64;
+        self.rolls += result.rolls as u64;
+        self
+    }
+
+    pub fn merge(self, other: Summary) -> Self {
+        Summary {
+            games: self.games + other.games,
+            wins: self.wins + other.wins,
+            damage: self.damage + other.damage,
+            squirrels: self.squirrels + other.squirrels,
+            returns: self.returns + other.returns,
+            rolls: self.rolls + other.rolls,
+        }
+    }
+
+    pub fn win_percentage(&self) -> f64 {
+        100.0 * self.wins as f64 / self.games.max(1) as f64
+    }
+
+    pub fn average(&self, total: u64) -> f64 {
+        total as f64 / self.games.max(1) as f64
+    }
+}
+
+/// A point of the parameter grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Point {
+    pub advantage: u32,
+    pub loyalty: u32,
+    pub damage: u32,
+}
+
+impl Point {
+    /// The settings with the parameters of this point.
+    pub fn settings(&self, settings: &Settings) -> Settings {
+        let mut settings = settings.clone();
+        Parameter::Advantage.apply(self.advantage, &mut settings);
+        Parameter::Loyalty.apply(self.loyalty, &mut settings);
+        Parameter::Life.apply(self.damage, &mut settings);
+        settings
+    }
+}
+
+/// Every point of the grid, advantage changing the slowest.
+pub fn grid(advantage: &Axis, loyalty: &Axis, damage: &Axis) -> Vec<Point> {
+    let mut points = Vec::new();
+    for advantage in advantage.values.iter().copied() {
+        for loyalty in loyalty.values.iter().copied() {
+            for damage in damage.values.iter().copied() {
+                points.push(Point {
+                    advantage,
+                    loyalty,
+                    damage,
+                });
+            }
+        }
+    }
+
+    points
+}
+
+/// How the sweep results are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(Format::Markdown),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format \"{s}\", expected markdown, csv or json")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Row {
+    #[serde(flatten)]
+    point: Point,
+    win_percentage: f64,
+    squirrels: f64,
+    #[serde(rename = "damage_dealt")]
+    damage: f64,
+    returns: f64,
+    rolls: f64,
+}
+
+/// Writes the summary of each point of the grid. The Markdown table leaves out the parameters
+/// with a single value, like the Results table of the README.
+pub fn render(points: &[(Point, Summary)], format: Format) -> String {
+    let rows: Vec<Row> = points
+        .iter()
+        .map(|(point, summary)| Row {
+            point: *point,
+            win_percentage: summary.win_percentage(),
+            squirrels: summary.average(summary.squirrels),
+            damage: summary.average(summary.damage),
+            returns: summary.average(summary.returns),
+            rolls: summary.average(summary.rolls),
+        })
+        .collect();
+
+    match format {
+        Format::Json => serde_json::to_string_pretty(&rows).expect("sweep rows are serializable"),
+        Format::Csv => {
+            let mut csv = "advantage,loyalty,damage,win_percentage,squirrels,damage_dealt,returns,rolls\n".to_owned();
+            for row in rows.iter() {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4}",
+                    row.point.advantage,
+                    row.point.loyalty,
+                    row.point.damage,
+                    row.win_percentage,
+                    row.squirrels,
+                    row.damage,
+                    row.returns,
+                    row.rolls
+                );
+            }
+            csv
+        }
+        Format::Markdown => markdown(&rows),
+    }
+}
+
+/// A column of the Markdown table, with its header and the cell of each row.
+type Column = (&'static str, Box<dyn Fn(&Row) -> String>);
+
+fn markdown(rows: &[Row]) -> String {
+    let varies = |value: fn(&Point) -> u32| rows.iter().any(|row| value(&row.point) != value(&rows[0].point));
+
+    let mut columns: Vec<Column> = Vec::new();
+    if varies(|point| point.advantage) || rows.len() == 1 {
+        columns.push(("Additional roll effects", Box::new(|row| row.point.advantage.to_string())));
+    }
+    if varies(|point| point.loyalty) {
+        columns.push(("Loyalty", Box::new(|row| row.point.loyalty.to_string())));
+    }
+    if varies(|point| point.damage) {
+        columns.push(("Target damage", Box::new(|row| row.point.damage.to_string())));
+    }
+    columns.push(("Win %", Box::new(|row| format!("{:.2} %", row.win_percentage))));
+    columns.push(("Squirrels", Box::new(|row| format!("{:.2}", row.squirrels))));
+    columns.push(("Damage", Box::new(|row| format!("{:.2}", row.damage))));
+    columns.push(("Returns", Box::new(|row| format!("{:.2}", row.returns))));
+    columns.push(("Total rolls", Box::new(|row| format!("{:.2}", row.rolls))));
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|(_, cell)| cell(row)).collect())
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, (header, _))| {
+            cells
+                .iter()
+                .map(|row| row[index].len())
+                .chain([header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!(" {cell:<width$} "))
+            .collect();
+        format!("|{}|\n", cells.join("|"))
+    };
+
+    let mut table = line(columns.iter().map(|(header, _)| header.to_string()).collect());
+    table.push_str(&format!(
+        "|{}|\n",
+        widths.iter().map(|width| "-".repeat(width + 2)).collect::<Vec<_>>().join("|")
+    ));
+    for row in cells {
+        table.push_str(&line(row));
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_and_ranges() {
+        assert_eq!("0..3".parse::<Axis>().unwrap().values, vec![0, 1, 2, 3]);
+        assert_eq!("4..=7".parse::<Axis>().unwrap().values, vec![4, 5, 6, 7]);
+        assert_eq!("20, 40".parse::<Axis>().unwrap().values, vec![20, 40]);
+        assert_eq!(
+            "0..2,15,20".parse::<Axis>().unwrap().values,
+            vec![0, 1, 2, 15, 20]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_axes() {
+        for invalid in ["", "3..1", "-1", "a", "1,,2", "1..b"] {
+            assert!(invalid.parse::<Axis>().is_err(), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn grid_changes_advantage_the_slowest() {
+        let points = grid(
+            &"0,1".parse().unwrap(),
+            &"3".parse().unwrap(),
+            &"20,40".parse().unwrap(),
+        );
+
+        let values: Vec<(u32, u32)> = points.iter().map(|point| (point.advantage, point.damage)).collect();
+        assert_eq!(values, vec![(0, 20), (0, 40), (1, 20), (1, 40)]);
+        assert!(points.iter().all(|point| point.loyalty == 3));
+    }
+}
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use crate::game::{Game, GameResult, Outcome};
+use crate::strategy::Strategy;
+
+/// A named strategy taking part in a tournament.
+#[derive(Debug, Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub strategy: Arc<dyn Strategy>,
+}
+
+impl Entrant {
+    pub fn new(name: &str, strategy: Arc<dyn Strategy>) -> Self {
+        Self {
+            name: name.to_owned(),
+            strategy,
+        }
+    }
+}
+
+/// How a single strategy did over all of the games.
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub wins: usize,
+    pub win_percentage: f64,
+    pub average_damage: f64,
+}
+
+/// Difference between two strategies playing the same dice, with two-sided p-values.
+///
+/// Wins are compared with McNemar's test over the games only one of the strategies won, and
+/// damage with a paired z-test over the per game damage differences.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub first: String,
+    pub second: String,
+    pub win_difference: f64,
+    pub win_p_value: f64,
+    pub damage_difference: f64,
+    pub damage_p_value: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub games: usize,
+    pub standings: Vec<Standing>,
+    pub comparisons: Vec<Comparison>,
+}
+
+/// Plays one game for each entrant, seeding every game with the same seed so that the
+/// entrants roll identical dice.
+pub fn play_round(entrants: &[Entrant], seed: u64, new_game: impl Fn() -> Game) -> Vec<GameResult> {
+    entrants
+        .iter()
+        .map(|entrant| {
+            new_game()
+                .with_seed(seed)
+                .with_strategy(Arc::clone(&entrant.strategy))
+                .run()
+        })
+        .collect()
+}
+
+impl Tournament {
+    /// Collects the results of rounds played with `play_round`, in the order of the entrants.
+    pub fn new(entrants: &[Entrant], rounds: &[Vec<GameResult>]) -> Self {
+        let games = rounds.len();
+        let won = |result: &GameResult| matches!(result.outcome, Outcome::Win);
+
+        let standings = entrants
+            .iter()
+            .enumerate()
+            .map(|(index, entrant)| {
+                let wins = rounds.iter().filter(|round| won(&round[index])).count();
+                let damage: f64 = rounds
+                    .iter()
+                    .map(|round| round[index].total_damage as f64)
+                    .sum();
+
+                Standing {
+                    name: entrant.name.clone(),
+                    wins,
+                    win_percentage: 100.0 * wins as f64 / usize::max(games, 1) as f64,
+                    average_damage: damage / usize::max(games, 1) as f64,
+                }
+            })
+            .collect();
+
+        let mut comparisons = Vec::new();
+        for first in 0..entrants.len() {
+            for second in first + 1..entrants.len() {
+                let only_first = rounds
+                    .iter()
+                    .filter(|round| won(&round[first]) && !won(&round[second]))
+                    .count();
+                let only_second = rounds
+                    .iter()
+                    .filter(|round| !won(&round[first]) && won(&round[second]))
+                    .count();
+
+                let differences: Vec<f64> = rounds
+                    .iter()
+                    .map(|round| round[second].total_damage as f64 - round[first].total_damage as f64)
+                    .collect();
+
+                comparisons.push(Comparison {
+                    first: entrants[first].name.clone(),
+                    second: entrants[second].name.clone(),
+                    win_difference: 100.0 * (only_second as f64 - only_first as f64)
+                        / usize::max(games, 1) as f64,
+                    win_p_value: mcnemar(only_first, only_second),
+                    damage_difference: mean(&differences),
+                    damage_p_value: paired_z_test(&differences),
+                });
+            }
+        }
+
+        Self {
+            games,
+            standings,
+            comparisons,
+        }
+    }
+}
+
+/// Stars in the usual style for a p-value, or "n.s." when it isn't significant at 5%.
+pub fn significance(p_value: f64) -> &'static str {
+    if p_value < 0.001 {
+        "***"
+    } else if p_value < 0.01 {
+        "**"
+    } else if p_value < 0.05 {
+        "*"
+    } else {
+        "n.s."
+    }
+}
+
+fn mcnemar(only_first: usize, only_second: usize) -> f64 {
+    let discordant = (only_first + only_second) as f64;
+    if discordant == 0.0 {
+        return 1.0;
+    }
+
+    let z = (only_second as f64 - only_first as f64) / discordant.sqrt();
+    two_sided_p_value(z)
+}
+
+fn paired_z_test(differences: &[f64]) -> f64 {
+    let n = differences.len() as f64;
+    if n < 2.0 {
+        return 1.0;
+    }
+
+    let mean = mean(differences);
+    let variance = differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    if variance == 0.0 {
+        return if mean == 0.0 { 1.0 } else { 0.0 };
+    }
+
+    two_sided_p_value(mean / (variance / n).sqrt())
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / f64::max(values.len() as f64, 1.0)
+}
+
+fn two_sided_p_value(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, Abramowitz and Stegun 7.1.26 with an absolute error below 1.5e-7.
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+
+    polynomial * (-x * x).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn erfc_matches_known_values() {
+        assert_close(erfc(0.0), 1.0, 1e-6);
+        assert_close(erfc(0.5), 0.4795001, 1e-6);
+        assert_close(erfc(1.0), 0.1572992, 1e-6);
+        assert_close(erfc(2.0), 0.0046777, 1e-6);
+    }
+
+    #[test]
+    fn two_sided_p_value_is_symmetric() {
+        assert_close(two_sided_p_value(1.959964), 0.05, 1e-5);
+        assert_close(two_sided_p_value(-1.959964), 0.05, 1e-5);
+        assert_close(two_sided_p_value(0.0), 1.0, 1e-6);
+    }
+
+    #[test]
+    fn mcnemar_without_discordant_games_is_not_significant() {
+        assert_eq!(mcnemar(0, 0), 1.0);
+        assert_close(mcnemar(10, 10), 1.0, 1e-6);
+    }
+
+    #[test]
+    fn mcnemar_matches_the_normal_approximation() {
+        // z = (15 - 5) / sqrt(20)
+        assert_close(mcnemar(5, 15), 0.0253473, 1e-5);
+        assert_close(mcnemar(15, 5), 0.0253473, 1e-5);
+        assert!(mcnemar(0, 25) < 0.001);
+    }
+
+    #[test]
+    fn paired_z_test_without_variance() {
+        assert_eq!(paired_z_test(&[0.0, 0.0, 0.0]), 1.0);
+        assert_eq!(paired_z_test(&[2.0, 2.0, 2.0]), 0.0);
+        assert_eq!(paired_z_test(&[2.0]), 1.0);
+    }
+
+    #[test]
+    fn significance_stars() {
+        assert_eq!(significance(0.0005), "***");
+        assert_eq!(significance(0.005), "**");
+        assert_eq!(significance(0.03), "*");
+        assert_eq!(significance(0.2), "n.s.");
+    }
+}
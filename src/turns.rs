@@ -0,0 +1,213 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::game::{GameResult, Outcome};
+use crate::simulator::Settings;
+use crate::strategy::Strategy;
+
+/// How the opponents answer Comet on their turns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OpponentModel {
+    /// Chance of the opponents attacking Comet
+    pub attack_chance: f64,
+    /// Damage dealt to Comet by an attack
+    pub attack_damage: u32,
+    /// Chance of the opponents removing Comet, checked before the attack
+    pub removal_chance: f64,
+}
+
+/// Result of activating Comet over several turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turns {
+    /// The turn every opponent was eliminated on, starting from 1
+    pub won_turn: Option<usize>,
+    /// Turns Comet was still on the battlefield after, counting every remaining turn once the
+    /// game was won with Comet alive
+    pub survived: usize,
+}
+
+/// Plays up to `turns` turns, carrying Comet's loyalty, the squirrels and the opponents' life
+/// totals over from one turn to the next.
+///
+/// The squirrels lose haste at the end of the turn but can attack on the next one. The opponents'
+/// blockers are back every turn, and the game is lost once every Comet has left the battlefield.
+pub fn play(
+    settings: &Settings,
+    strategy: Arc<dyn Strategy>,
+    turns: usize,
+    opponents: OpponentModel,
+    seed: u64,
+) -> Turns {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut settings = settings.clone();
+
+    for turn in 1..=turns {
+        let result = settings
+            .game()
+            .with_strategy(Arc::clone(&strategy))
+            .with_seed(rng.gen())
+            .run();
+
+        let mut comets = carry_over(&mut settings, &result);
+
+        let comet_alive = !comets.is_empty();
+        if matches!(result.outcome, Outcome::Win) {
+            return Turns {
+                won_turn: Some(turn),
+                survived: if comet_alive { turns } else { turn - 1 },
+            };
+        }
+
+        if !comet_alive {
+            log::debug!("[Turn: {turn}] Comet died on its own turn.");
+            return Turns {
+                won_turn: None,
+                survived: turn - 1,
+            };
+        }
+
+        // The opponents go after the first of the Comets still on the battlefield
+        if rng.gen_bool(opponents.removal_chance) {
+            log::debug!("[Turn: {turn}] The opponents removed Comet.");
+            comets.remove(0);
+        } else if rng.gen_bool(opponents.attack_chance) {
+            comets[0] -= opponents.attack_damage as i32;
+            log::debug!(
+                "[Turn: {turn}] The opponents attacked Comet for {}, {} loyalty left.",
+                opponents.attack_damage,
+                comets[0]
+            );
+
+            if comets[0] <= 0 {
+                comets.remove(0);
+            }
+        }
+
+        if comets.is_empty() {
+            return Turns {
+                won_turn: None,
+                survived: turn - 1,
+            };
+        }
+
+        settings.loyalty = comets[0];
+        settings.board.extra_comets = comets[1..].to_vec();
+    }
+
+    Turns {
+        won_turn: None,
+        survived: turns,
+    }
+}
+
+/// Carries the squirrels still on the battlefield and the opponents' life totals over to the
+/// next turn, returning the loyalties of the Comets still on the battlefield.
+///
+/// The squirrels that were sacrificed, or blocked and killed in combat, are gone.
+pub fn carry_over(settings: &mut Settings, result: &GameResult) -> Vec<i32> {
+    settings.board.squirrels = settings
+        .board
+        .squirrels
+        .saturating_add(result.squirrels)
+        .saturating_sub(result.sacrificed + result.blocked);
+    settings.board.opponents = result.life_left.clone();
+
+    result
+        .loyalties
+        .iter()
+        .copied()
+        .filter(|loyalty| *loyalty > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::{Ability, Band, Effect};
+    use crate::combat::CombatTiming;
+    use crate::game::Board;
+    use crate::strategy::DefaultStrategy;
+
+    /// An ability creating a squirrel and then losing all of the loyalty, activated once a turn.
+    fn one_shot(loyalty: i32) -> Ability {
+        Ability {
+            name: "One shot".to_owned(),
+            red: false,
+            die: 1,
+            bands: vec![Band {
+                from: 1,
+                to: 1,
+                effects: vec![Effect::Squirrels(1), Effect::Loyalty(-loyalty)],
+            }],
+        }
+    }
+
+    fn settings(board: Board) -> Settings {
+        Settings {
+            squirrels: true,
+            advantage: 0,
+            loyalty: 5,
+            damage: 100,
+            board,
+        }
+    }
+
+    #[test]
+    fn blocked_squirrels_dont_carry_over() {
+        let mut settings = settings(Board {
+            ability: one_shot(5),
+            squirrels: 3,
+            blockers: vec![2, 2],
+            combat_timing: CombatTiming::AfterCombat,
+            ..Board::default()
+        });
+
+        let result = settings.game().with_seed(1).run();
+        assert_eq!((result.attackers, result.blocked), (3, 2));
+
+        let comets = carry_over(&mut settings, &result);
+        assert!(comets.is_empty());
+        // The one unblocked squirrel and the one created stay, the two blocked ones died
+        assert_eq!(settings.board.squirrels, 2);
+        assert_eq!(settings.board.opponents, vec![99]);
+    }
+
+    #[test]
+    fn blockers_keep_killing_squirrels_every_turn() {
+        let mut settings = settings(Board {
+            ability: one_shot(1),
+            squirrels: 4,
+            blockers: vec![2, 2],
+            combat_timing: CombatTiming::AfterCombat,
+            ..Board::default()
+        });
+
+        // Each turn the ability creates a squirrel and the two blockers kill two of them
+        settings.loyalty = 4;
+        for expected in [3, 2, 1] {
+            let result = settings.game().with_seed(1).run();
+            let comets = carry_over(&mut settings, &result);
+            assert_eq!(settings.board.squirrels, expected);
+            settings.loyalty = comets[0];
+        }
+    }
+
+    #[test]
+    fn play_loses_once_comet_is_gone() {
+        let settings = settings(Board {
+            ability: one_shot(5),
+            ..Board::default()
+        });
+
+        let turns = play(&settings, Arc::new(DefaultStrategy), 3, OpponentModel::default(), 1);
+        assert_eq!(
+            turns,
+            Turns {
+                won_turn: None,
+                survived: 0
+            }
+        );
+    }
+}